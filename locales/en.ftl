@@ -0,0 +1,251 @@
+# English messages of the interface, the fallback of the other catalogs.
+# Menu labels must not contain `/`, which separates submenus.
+# Messages depending on a number select their form by `$count`.
+
+## Menus
+
+menu-file = File
+menu-edit = Edit
+menu-view = View
+menu-help = Help
+menu-import = Import
+menu-export = Export
+menu-sort = Sort
+menu-theme = Theme
+menu-scheme = Scheme
+menu-new-data-file = New data file…
+menu-open-data-file = Open data file…
+menu-markdown = Markdown checklist…
+menu-paste-markdown = Paste Markdown checklist…
+menu-copy-markdown = Copy Markdown checklist
+menu-org = Org file…
+menu-taskwarrior = Taskwarrior JSON…
+menu-html-report = HTML report…
+menu-print-report = Print report…
+menu-preferences = Preferences…
+menu-quit = Quit
+menu-undo = Undo
+menu-redo = Redo
+menu-new-todo = New to-do
+menu-toggle-completed = Toggle completed
+menu-edit-description = Edit description…
+menu-duplicate = Duplicate
+menu-copy-text = Copy text
+menu-history = History…
+menu-archive-completed = Archive completed
+menu-trash = Trash…
+menu-select-all = Select all
+menu-delete = Delete
+menu-filter = Filter
+menu-archive = Archive…
+menu-shortcuts = Keyboard shortcuts
+menu-about = About
+
+sort-newest-first = Newest first
+sort-oldest-first = Oldest first
+sort-description = Description
+sort-pending-first = Pending first
+
+theme-follow-system = Follow system
+
+## Context menu
+
+action-mark-done = Mark as done
+action-mark-pending = Mark as pending
+action-edit = Edit description…
+action-duplicate = Duplicate
+action-copy = Copy text
+action-history = History…
+action-archive = Archive
+action-delete = Delete
+
+## Main window
+
+label-filter-prefix = Filter prefix:
+label-sort = Sort:
+label-description = Description:
+label-completed = Completed:
+column-description = DESCRIPTION
+column-datetime = DATETIME
+column-completed = COMPLETED
+hint-shortcuts = Press { $shortcut } for the keyboard shortcuts
+
+## Buttons
+
+button-ok = OK
+button-cancel = Cancel
+button-save = Save
+button-close = Close
+button-create = Create
+button-update = Update
+button-delete = Delete
+button-import = Import
+button-browse = Browse…
+button-restore = Restore
+button-unarchive = Unarchive
+button-empty-trash = Empty trash
+button-empty-trash-ellipsis = Empty trash…
+button-nested = Nested
+button-flattened = Flattened
+
+## Dialogs
+
+title-new-data-file = New data file
+title-open-data-file = Open data file
+title-data-file = Data file
+title-export-markdown = Export Markdown checklist
+title-import-markdown = Import Markdown checklist
+title-paste-markdown = Paste a Markdown checklist
+title-export-org = Export Org file
+title-import-org = Import Org file
+title-export-taskwarrior = Export Taskwarrior JSON
+title-import-taskwarrior = Import Taskwarrior JSON
+title-html-report = HTML report
+title-export-report = Export HTML report
+title-print-report = Print report
+title-batch = Create several to-dos
+title-trash = Trash
+title-archive = Archive
+title-history = History of "{ $description }"
+title-preferences = Preferences
+title-shortcuts = Keyboard shortcuts
+label-group-by = Group by:
+label-trim-bullets = Trim bullets and numbering
+label-check-all = Check all
+label-search = Search:
+
+question-delete-one = Move "{ $description }" to the trash?
+question-delete-many = Move { $count } items to the trash?
+question-empty-trash = Delete the { $count } to-dos of the trash for good?
+question-org-nesting = How should nested headlines be imported?
+
+alert-read-file = Could not read { $path }: { $error }
+alert-write-file = Could not write { $path }: { $error }
+alert-read-archive = Could not read the archive: { $error }
+alert-read-history = Could not read the history: { $error }
+alert-not-task-export = Not a `task export` file: { $error }
+alert-date-format = Invalid date format: { $format }
+alert-data-file-open = { $path } is open in another window
+alert-new-data-file-open = { $path } is the open data file: pick another name for the new one
+alert-lock-data-file = Could not lock { $path }: { $error }
+
+about =
+    Todolist RS { $version }
+    Fltk todolist demo app
+
+## Preferences
+
+pref-theme = Theme:
+pref-scheme = Widget scheme:
+pref-date-display = Date display:
+pref-custom-format = Custom format:
+pref-data-file = Data file:
+pref-font-size = Font size:
+pref-confirm-delete = Confirm before deleting
+pref-autosave = Save every change immediately
+pref-trash-days = Trash kept (days):
+pref-archive-days = Archive done after (days):
+pref-language = Language:
+language-system = System default
+tooltip-custom-format = strftime pattern, e.g. %Y-%m-%d %H:%M
+tooltip-autosave = Otherwise the list is saved when the application quits
+tooltip-trash-days = 0 keeps deleted to-dos until the trash is emptied
+tooltip-archive-days = 0 archives completed to-dos only from the Edit menu
+tooltip-language = Applies the next time the application starts
+
+## Keyboard shortcuts
+
+shortcut-new-todo = New to-do
+shortcut-create = Create the to-do
+shortcut-toggle-completed = Toggle completed
+shortcut-edit = Edit the description
+shortcut-duplicate = Duplicate the selected to-dos
+shortcut-copy = Copy the selected descriptions
+shortcut-history = History of the to-do
+shortcut-delete = Delete the selected to-dos
+shortcut-context-menu = Actions on the selected to-dos
+shortcut-select-all = Select all
+shortcut-previous = Previous to-do
+shortcut-next = Next to-do
+shortcut-filter = Filter
+shortcut-undo = Undo
+shortcut-redo = Redo
+shortcut-new-data-file = New data file
+shortcut-open-data-file = Open data file
+shortcut-print = Print report
+shortcut-preferences = Preferences
+shortcut-shortcuts = Keyboard shortcuts
+shortcut-quit = Quit
+
+## Dates
+
+date-display-locale = Locale default
+date-display-iso-8601 = ISO 8601
+date-display-relative = Relative
+date-display-custom = Custom
+date-just-now = just now
+date-ago = { $time } ago
+date-in = in { $time }
+date-minutes = { $count ->
+    [one] { $count } minute
+   *[other] { $count } minutes
+}
+date-hours = { $count ->
+    [one] { $count } hour
+   *[other] { $count } hours
+}
+date-days = { $count ->
+    [one] { $count } day
+   *[other] { $count } days
+}
+date-months = { $count ->
+    [one] { $count } month
+   *[other] { $count } months
+}
+date-years = { $count ->
+    [one] { $count } year
+   *[other] { $count } years
+}
+
+## History
+
+event-created = Created
+event-renamed = Renamed to "{ $description }"
+event-completed = Marked as done
+event-uncompleted = Marked as pending
+event-deleted = Moved to the trash
+event-restored = Restored
+event-edited = Changed by undo or redo
+event-archived = Archived
+event-unarchived = Unarchived
+event-purged = Emptied from the trash
+
+## Reports
+
+report-title = Todolist RS report
+report-summary = { $total } items • { $completed } completed • { $pending } pending • generated { $generated }
+report-description = Description
+report-created = Created
+report-deadline = Deadline
+report-status = Status
+report-completed = Completed
+report-pending = Pending
+report-priority = Priority { $priority }
+report-no-priority = No priority
+report-untagged = Untagged
+grouping-none = None
+grouping-status = Status
+grouping-priority = Priority
+grouping-tag = Tag
+
+## Taskwarrior
+
+import-skipped = { $count ->
+    [one] { $count } deleted task skipped
+   *[other] { $count } deleted tasks skipped
+}
+import-unsupported = Fields not supported:
+import-tasks = { $count ->
+    [one] { $count } task
+   *[other] { $count } tasks
+}
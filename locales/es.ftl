@@ -0,0 +1,251 @@
+# Mensajes en español de la interfaz.
+# Las etiquetas de los menús no pueden contener `/`, que separa los submenús.
+# Los mensajes que dependen de un número eligen su forma según `$count`.
+
+## Menús
+
+menu-file = Archivo
+menu-edit = Editar
+menu-view = Ver
+menu-help = Ayuda
+menu-import = Importar
+menu-export = Exportar
+menu-sort = Ordenar
+menu-theme = Tema
+menu-scheme = Esquema
+menu-new-data-file = Nuevo archivo de datos…
+menu-open-data-file = Abrir archivo de datos…
+menu-markdown = Lista de Markdown…
+menu-paste-markdown = Pegar lista de Markdown…
+menu-copy-markdown = Copiar lista de Markdown
+menu-org = Archivo Org…
+menu-taskwarrior = JSON de Taskwarrior…
+menu-html-report = Informe HTML…
+menu-print-report = Imprimir informe…
+menu-preferences = Preferencias…
+menu-quit = Salir
+menu-undo = Deshacer
+menu-redo = Rehacer
+menu-new-todo = Nueva tarea
+menu-toggle-completed = Marcar o desmarcar como hecha
+menu-edit-description = Editar descripción…
+menu-duplicate = Duplicar
+menu-copy-text = Copiar texto
+menu-history = Historial…
+menu-archive-completed = Archivar las hechas
+menu-trash = Papelera…
+menu-select-all = Seleccionar todo
+menu-delete = Eliminar
+menu-filter = Filtrar
+menu-archive = Archivo de tareas…
+menu-shortcuts = Atajos de teclado
+menu-about = Acerca de
+
+sort-newest-first = Más recientes primero
+sort-oldest-first = Más antiguas primero
+sort-description = Descripción
+sort-pending-first = Pendientes primero
+
+theme-follow-system = Seguir al sistema
+
+## Menú contextual
+
+action-mark-done = Marcar como hecha
+action-mark-pending = Marcar como pendiente
+action-edit = Editar descripción…
+action-duplicate = Duplicar
+action-copy = Copiar texto
+action-history = Historial…
+action-archive = Archivar
+action-delete = Eliminar
+
+## Ventana principal
+
+label-filter-prefix = Filtrar por prefijo:
+label-sort = Ordenar:
+label-description = Descripción:
+label-completed = Hecha:
+column-description = DESCRIPCIÓN
+column-datetime = FECHA Y HORA
+column-completed = HECHA
+hint-shortcuts = Pulse { $shortcut } para ver los atajos de teclado
+
+## Botones
+
+button-ok = Aceptar
+button-cancel = Cancelar
+button-save = Guardar
+button-close = Cerrar
+button-create = Crear
+button-update = Actualizar
+button-delete = Eliminar
+button-import = Importar
+button-browse = Examinar…
+button-restore = Restaurar
+button-unarchive = Desarchivar
+button-empty-trash = Vaciar la papelera
+button-empty-trash-ellipsis = Vaciar la papelera…
+button-nested = Anidados
+button-flattened = Aplanados
+
+## Diálogos
+
+title-new-data-file = Nuevo archivo de datos
+title-open-data-file = Abrir archivo de datos
+title-data-file = Archivo de datos
+title-export-markdown = Exportar lista de Markdown
+title-import-markdown = Importar lista de Markdown
+title-paste-markdown = Pegar una lista de Markdown
+title-export-org = Exportar archivo Org
+title-import-org = Importar archivo Org
+title-export-taskwarrior = Exportar JSON de Taskwarrior
+title-import-taskwarrior = Importar JSON de Taskwarrior
+title-html-report = Informe HTML
+title-export-report = Exportar informe HTML
+title-print-report = Imprimir informe
+title-batch = Crear varias tareas
+title-trash = Papelera
+title-archive = Archivo de tareas
+title-history = Historial de «{ $description }»
+title-preferences = Preferencias
+title-shortcuts = Atajos de teclado
+label-group-by = Agrupar por:
+label-trim-bullets = Quitar viñetas y numeración
+label-check-all = Marcar todas
+label-search = Buscar:
+
+question-delete-one = ¿Mover «{ $description }» a la papelera?
+question-delete-many = ¿Mover { $count } tareas a la papelera?
+question-empty-trash = ¿Eliminar definitivamente las { $count } tareas de la papelera?
+question-org-nesting = ¿Cómo se deben importar los encabezados anidados?
+
+alert-read-file = No se pudo leer { $path }: { $error }
+alert-write-file = No se pudo escribir { $path }: { $error }
+alert-read-archive = No se pudo leer el archivo de tareas: { $error }
+alert-read-history = No se pudo leer el historial: { $error }
+alert-not-task-export = No es un archivo de `task export`: { $error }
+alert-date-format = Formato de fecha no válido: { $format }
+alert-data-file-open = { $path } está abierto en otra ventana
+alert-new-data-file-open = { $path } es el archivo de datos abierto: elige otro nombre para el nuevo
+alert-lock-data-file = No se pudo bloquear { $path }: { $error }
+
+about =
+    Todolist RS { $version }
+    Aplicación de demostración de lista de tareas con FLTK
+
+## Preferencias
+
+pref-theme = Tema:
+pref-scheme = Esquema de controles:
+pref-date-display = Mostrar fechas:
+pref-custom-format = Formato propio:
+pref-data-file = Archivo de datos:
+pref-font-size = Tamaño de letra:
+pref-confirm-delete = Confirmar antes de eliminar
+pref-autosave = Guardar cada cambio al momento
+pref-trash-days = Días en la papelera:
+pref-archive-days = Archivar las hechas tras (días):
+pref-language = Idioma:
+language-system = Predeterminado del sistema
+tooltip-custom-format = Patrón de strftime, p. ej. %d/%m/%Y %H:%M
+tooltip-autosave = Si no, la lista se guarda al salir de la aplicación
+tooltip-trash-days = 0 conserva las tareas eliminadas hasta vaciar la papelera
+tooltip-archive-days = 0 archiva las tareas hechas solo desde el menú Editar
+tooltip-language = Se aplica la próxima vez que se inicie la aplicación
+
+## Atajos de teclado
+
+shortcut-new-todo = Nueva tarea
+shortcut-create = Crear la tarea
+shortcut-toggle-completed = Marcar o desmarcar como hecha
+shortcut-edit = Editar la descripción
+shortcut-duplicate = Duplicar las tareas seleccionadas
+shortcut-copy = Copiar las descripciones seleccionadas
+shortcut-history = Historial de la tarea
+shortcut-delete = Eliminar las tareas seleccionadas
+shortcut-context-menu = Acciones sobre las tareas seleccionadas
+shortcut-select-all = Seleccionar todo
+shortcut-previous = Tarea anterior
+shortcut-next = Tarea siguiente
+shortcut-filter = Filtrar
+shortcut-undo = Deshacer
+shortcut-redo = Rehacer
+shortcut-new-data-file = Nuevo archivo de datos
+shortcut-open-data-file = Abrir archivo de datos
+shortcut-print = Imprimir informe
+shortcut-preferences = Preferencias
+shortcut-shortcuts = Atajos de teclado
+shortcut-quit = Salir
+
+## Fechas
+
+date-display-locale = Según la configuración regional
+date-display-iso-8601 = ISO 8601
+date-display-relative = Relativas
+date-display-custom = Formato propio
+date-just-now = ahora mismo
+date-ago = hace { $time }
+date-in = dentro de { $time }
+date-minutes = { $count ->
+    [one] { $count } minuto
+   *[other] { $count } minutos
+}
+date-hours = { $count ->
+    [one] { $count } hora
+   *[other] { $count } horas
+}
+date-days = { $count ->
+    [one] { $count } día
+   *[other] { $count } días
+}
+date-months = { $count ->
+    [one] { $count } mes
+   *[other] { $count } meses
+}
+date-years = { $count ->
+    [one] { $count } año
+   *[other] { $count } años
+}
+
+## Historial
+
+event-created = Creada
+event-renamed = Renombrada a «{ $description }»
+event-completed = Marcada como hecha
+event-uncompleted = Marcada como pendiente
+event-deleted = Movida a la papelera
+event-restored = Restaurada
+event-edited = Cambiada al deshacer o rehacer
+event-archived = Archivada
+event-unarchived = Desarchivada
+event-purged = Eliminada de la papelera
+
+## Informes
+
+report-title = Informe de Todolist RS
+report-summary = { $total } tareas • { $completed } hechas • { $pending } pendientes • generado el { $generated }
+report-description = Descripción
+report-created = Creada
+report-deadline = Fecha límite
+report-status = Estado
+report-completed = Hecha
+report-pending = Pendiente
+report-priority = Prioridad { $priority }
+report-no-priority = Sin prioridad
+report-untagged = Sin etiquetas
+grouping-none = Ninguno
+grouping-status = Estado
+grouping-priority = Prioridad
+grouping-tag = Etiqueta
+
+## Taskwarrior
+
+import-skipped = { $count ->
+    [one] { $count } tarea eliminada omitida
+   *[other] { $count } tareas eliminadas omitidas
+}
+import-unsupported = Campos no admitidos:
+import-tasks = { $count ->
+    [one] { $count } tarea
+   *[other] { $count } tareas
+}
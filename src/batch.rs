@@ -0,0 +1,173 @@
+use std::fs;
+
+/// Split pasted or dropped text into candidate to-do descriptions, one per non-empty line,
+/// each with the index of its line in the text
+pub fn split_candidates(text: &str, trim_markers: bool) -> Vec<(usize, String)> {
+    text.lines()
+        .map(|line| match trim_markers {
+            true => strip_list_marker(line.trim()),
+            false => line.trim(),
+        })
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(index, line)| (index, line.to_string()))
+        .collect()
+}
+
+/// Remove a leading bullet (`-`, `*`, `+`, `•`), checkbox (`[ ]`, `[x]`)
+/// or number (`1.`, `2)`) from a list line. A line with only a marker is left empty
+fn strip_list_marker(line: &str) -> &str {
+    let mut line = line;
+
+    for bullet in ["-", "*", "+", "•"] {
+        if let Some(rest) = strip_marker(line, bullet) {
+            line = rest;
+            break;
+        }
+    }
+
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 {
+        if let Some(rest) =
+            strip_marker(&line[digits..], ".").or_else(|| strip_marker(&line[digits..], ")"))
+        {
+            line = rest;
+        }
+    }
+
+    for checkbox in ["[ ]", "[x]", "[X]"] {
+        if let Some(rest) = strip_marker(line, checkbox) {
+            line = rest;
+            break;
+        }
+    }
+
+    line
+}
+
+/// The line after the marker, if the marker is followed by a space or ends the line
+fn strip_marker<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    line.strip_prefix(marker)
+        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        .map(str::trim_start)
+}
+
+/// Read the text files dropped on the window.
+/// The drop gives one path or `file://` URI per line
+pub fn read_dropped_files(paths: &str) -> String {
+    paths
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            let path = match line.strip_prefix("file://") {
+                Some(uri) => percent_decode(uri),
+                None => line.to_string(),
+            };
+            fs::read_to_string(&path)
+                .map_err(|err| eprintln!("{path}: {err:?}"))
+                .ok()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Decode the `%XX` escapes of a URI path
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn descriptions(text: &str, trim_markers: bool) -> Vec<String> {
+        let candidates = split_candidates(text, trim_markers).into_iter();
+        candidates.map(|(_, description)| description).collect()
+    }
+
+    #[test]
+    fn list_markers_are_trimmed() {
+        let text =
+            "- buy milk\n* call Bob\n+ read\n• walk\n1. wash\n12) cook\n- [ ] pay\n[x] sleep";
+        assert_eq!(
+            descriptions(text, true),
+            ["buy milk", "call Bob", "read", "walk", "wash", "cook", "pay", "sleep"]
+        );
+    }
+
+    #[test]
+    fn markers_are_kept_unless_asked() {
+        assert_eq!(
+            descriptions("- buy milk\n1) call Bob", false),
+            ["- buy milk", "1) call Bob"]
+        );
+    }
+
+    #[test]
+    fn words_that_look_like_markers_are_kept() {
+        assert_eq!(
+            descriptions("-5 degrees\n2024 plans\n1.5 litres\n*bold*", true),
+            ["-5 degrees", "2024 plans", "1.5 litres", "*bold*"]
+        );
+    }
+
+    #[test]
+    fn blank_lines_and_carriage_returns_are_dropped() {
+        let text = "\r\n  buy milk  \r\n\r\n - \r\n2.\r\n\tcall Bob\r\n";
+        assert_eq!(
+            split_candidates(text, true),
+            [(1, "buy milk".to_string()), (5, "call Bob".to_string())]
+        );
+        assert_eq!(
+            descriptions(text, false),
+            ["buy milk", "-", "2.", "call Bob"]
+        );
+    }
+
+    #[test]
+    fn dropped_paths_and_uris_are_read() {
+        let dir = env::temp_dir().join(format!("todolist-batch-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("first list.txt");
+        let second = dir.join("second.txt");
+        fs::write(&first, "buy milk").unwrap();
+        fs::write(&second, "call Bob").unwrap();
+
+        let uri = format!("file://{}", first.display()).replace(' ', "%20");
+        let missing = dir.join("missing.txt");
+        let paths = format!("{uri}\r\n\n{}\n{}\n", second.display(), missing.display());
+        assert_eq!(read_dropped_files(&paths), "buy milk\ncall Bob");
+        fs::remove_dir_all(&dir).unwrap_or_default();
+    }
+
+    #[test]
+    fn escapes_are_decoded() {
+        assert_eq!(percent_decode("/tmp/a%20b%C3%A9"), "/tmp/a bé");
+        assert_eq!(percent_decode("/tmp/100%"), "/tmp/100%");
+        assert_eq!(percent_decode("/tmp/%zz"), "/tmp/%zz");
+    }
+}
@@ -0,0 +1,184 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use fltk::app;
+use fltk_theme::{WidgetScheme, WidgetTheme};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{DATA_PATH, DATETIME_FORMAT},
+    datetime::{DateDisplay, DateFormat},
+    theme::{resolve_scheme, resolve_theme, Palette},
+};
+
+/// User preferences, stored as TOML in the user's config directory
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Name of an entry of `THEMES`, or `SYSTEM_THEME`
+    pub theme: String,
+    /// Name of an entry of `SCHEMES`
+    pub scheme: String,
+    pub date_display: DateDisplay,
+    /// `strftime` pattern of the custom date display
+    pub datetime_format: String,
+    pub data_path: String,
+    pub font_size: i32,
+    pub confirm_delete: bool,
+    /// Write every change immediately instead of when the app quits
+    pub autosave: bool,
+    /// Days deleted items stay in the trash, 0 to keep them until it is emptied
+    pub trash_days: u32,
+    /// Days after which completed items are archived, 0 to archive them only by hand
+    pub archive_days: u32,
+    /// Code of the language of the interface, empty to follow the `LANG` of the system.
+    /// Read at startup
+    pub language: String,
+    /// Key bindings overriding the defaults, by action name (see `keymap.rs`).
+    /// Read at startup
+    pub shortcuts: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: "Dark".to_string(),
+            scheme: "Aqua".to_string(),
+            date_display: DateDisplay::default(),
+            datetime_format: DATETIME_FORMAT.to_string(),
+            data_path: DATA_PATH.to_string(),
+            font_size: 14,
+            confirm_delete: false,
+            autosave: true,
+            trash_days: 30,
+            archive_days: 0,
+            language: String::new(),
+            shortcuts: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Apply the theme, scheme and default font size, and return the colours
+    /// matching the theme. Only widgets created afterwards get the font size
+    pub fn apply(&self) -> Palette {
+        let theme = resolve_theme(&self.theme);
+        WidgetTheme::new(theme).apply();
+        if let Some(scheme) = resolve_scheme(&self.scheme) {
+            WidgetScheme::new(scheme).apply();
+        }
+        app::set_font_size(self.font_size);
+        app::redraw();
+        Palette::new(theme)
+    }
+
+    /// How to show dates, following the locale of the system if it is the display
+    pub fn date_format(&self) -> DateFormat {
+        DateFormat::new(self.date_display, &self.datetime_format)
+    }
+}
+
+/// Location of the config file: `$XDG_CONFIG_HOME/rust-fltk-todolist/config.toml`
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("rust-fltk-todolist").join("config.toml"))
+}
+
+/// Save the preferences to the config file, if there is one
+pub fn dump_config(config: &Config, path: Option<&Path>) {
+    let Some(path) = path else {
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|err| eprintln!("{err:?}"))
+            .unwrap_or_default();
+    }
+    fs::write(path, toml::to_string_pretty(config).unwrap())
+        .map_err(|err| eprintln!("{err:?}"))
+        .unwrap_or_default();
+}
+
+/// Load the preferences from the config file, if there is one
+pub fn load_config(path: Option<&Path>) -> Config {
+    path.and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| {
+            toml::from_str(&data)
+                .map_err(|err| eprintln!("{err:?}"))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    /// A config file of its own for each test
+    fn config_file(name: &str, text: Option<&str>) -> PathBuf {
+        let path = env::temp_dir()
+            .join(format!("todolist-config-{}-{name}", process::id()))
+            .join("config.toml");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        if let Some(text) = text {
+            fs::write(&path, text).unwrap();
+        }
+        path
+    }
+
+    fn remove(path: &Path) {
+        fs::remove_dir_all(path.parent().unwrap()).unwrap_or_default();
+    }
+
+    #[test]
+    fn missing_files_give_the_defaults() {
+        let path = config_file("missing", None);
+        let config = load_config(Some(&path));
+        assert!(config == Config::default());
+        assert!(load_config(None) == Config::default());
+        assert_eq!(config.theme, "Dark");
+        assert_eq!(config.data_path, DATA_PATH);
+        assert!(config.autosave);
+        assert_eq!(config.trash_days, 30);
+        remove(&path);
+    }
+
+    #[test]
+    fn missing_keys_keep_their_default_and_unknown_ones_are_ignored() {
+        let text = "font_size = 18\nfuture_option = true\n\n[shortcuts]\nedit = \"Ctrl+E\"\n";
+        let path = config_file("partial", Some(text));
+        let config = load_config(Some(&path));
+        assert_eq!(config.font_size, 18);
+        assert_eq!(config.shortcuts["edit"], "Ctrl+E");
+        assert_eq!(config.theme, Config::default().theme);
+        assert_eq!(config.date_display, DateDisplay::default());
+        remove(&path);
+    }
+
+    #[test]
+    fn invalid_files_give_the_defaults() {
+        let path = config_file("invalid", Some("font_size = \"large\"\n"));
+        assert!(load_config(Some(&path)) == Config::default());
+        remove(&path);
+    }
+
+    #[test]
+    fn saved_preferences_are_loaded_back() {
+        let path = config_file("saved", None);
+        let config = Config {
+            theme: "Blue".to_string(),
+            date_display: DateDisplay::Custom,
+            datetime_format: "%d %B".to_string(),
+            language: "es".to_string(),
+            shortcuts: BTreeMap::from([("quit".to_string(), "none".to_string())]),
+            ..Default::default()
+        };
+        dump_config(&config, Some(&path));
+        assert!(load_config(Some(&path)) == config);
+        remove(&path);
+    }
+}
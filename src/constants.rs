@@ -1,9 +1,29 @@
-pub const DATA_PATH: &'static str = ".todo.dat";
+use std::time::Duration;
+
+pub const DATA_PATH: &str = ".todo.dat";
+
+pub const DATETIME_FORMAT: &str = "%d-%m-%Y • %H:%M:%S";
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+pub const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Time between two checks of the desktop colour scheme
+pub const SYSTEM_THEME_POLL: Duration = Duration::from_secs(5);
+/// Seconds between redraws of the dates, for relative ones and time zone changes
+pub const DATES_REFRESH: f64 = 60.0;
 
 pub const WIDGET_HEIGHT: i32 = 25;
 pub const WIDGET_PADDING: i32 = 10;
 pub const WIDGET_WIDTH: i32 = 70;
 
+pub const WINDOW_WIDTH: i32 = WIDGET_WIDTH * 12;
+pub const WINDOW_HEIGHT: i32 = WIDGET_HEIGHT * 16;
+pub const WINDOW_MIN_WIDTH: i32 = WIDGET_WIDTH * 6;
+pub const WINDOW_MIN_HEIGHT: i32 = WIDGET_HEIGHT * 12;
+/// Below this window width the form is placed under the list
+pub const NARROW_WIDTH: i32 = WIDGET_WIDTH * 10;
+pub const FORM_WIDTH: i32 = WIDGET_WIDTH * 4;
+pub const FORM_HEIGHT: i32 = WIDGET_HEIGHT * 2 + WIDGET_PADDING;
+
 /* pub const ICON: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
 <!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
 <svg width="100%" height="100%" viewBox="0 0 1200 800" version="1.1" xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" xml:space="preserve" xmlns:serif="http://www.serif.com/" style="fill-rule:evenodd;clip-rule:evenodd;stroke-linejoin:round;stroke-miterlimit:1.41421;">
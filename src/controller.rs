@@ -1,58 +1,122 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
 use fltk::{
-    app::{channel, event_key, App, Receiver, Scheme, Sender},
-    enums::{Event, Key},
+    app::{self, channel, event_key, event_state, App, MouseButton, Receiver, Scheme, Sender},
+    enums::{Event, Key, Shortcut},
     image,
-    prelude::{GroupExt, WidgetBase, WidgetExt, WindowExt},
+    prelude::{GroupExt, InputExt, WidgetBase, WidgetExt, WindowExt},
     window::Window,
 };
-use fltk_theme::{ThemeType, WidgetScheme, WidgetTheme};
 use rust_embed::RustEmbed;
+use todolist_core::update::{self, State};
 
 use crate::{
-    constants::WIDGET_PADDING,
-    utils::{draw_ui, message_waiting_loop, MainWindow, Message},
+    batch::read_dropped_files,
+    config::{config_path, Config},
+    constants::{DATES_REFRESH, WINDOW_HEIGHT, WINDOW_MIN_HEIGHT, WINDOW_MIN_WIDTH, WINDOW_WIDTH},
+    i18n::set_language,
+    instance::{Command, Instance},
+    keymap::Keymap,
+    session::{is_on_screen, load_session, sessions_path, Session},
+    table::{COMPLETED_COLUMN, DESCRIPTION_COLUMN},
+    theme::{watch_system_theme, Palette},
+    utils::{
+        draw_ui, fit_layout, listen, message_waiting_loop, restore_state, show_theme, MainWindow,
+        Message,
+    },
 };
 
 #[derive(RustEmbed)]
 #[folder = "assets/"]
 struct Asset;
 
+/// Files kept by the app besides the data file
+pub struct Files {
+    /// `None` without a config directory, the preferences being left unsaved
+    pub config: Option<PathBuf>,
+    /// `None` without a data directory, the window starting afresh each time
+    pub sessions: Option<PathBuf>,
+}
+
+impl Files {
+    /// The config file and the sessions file of the user
+    pub fn standard() -> Self {
+        Self {
+            config: config_path(),
+            sessions: sessions_path(),
+        }
+    }
+}
+
 pub struct TodolistRS {
     pub a: App,
+    pub config: Config,
+    pub files: Files,
+    /// Lock of the data file
+    pub instance: Instance,
+    pub keymap: Keymap,
     pub m_window: MainWindow,
+    /// Colours matching the theme in use
+    pub palette: Palette,
     pub r: Receiver<Message>,
     pub s: Sender<Message>,
+    pub session: Session,
+    /// What the window shows, changed by `update::update`
+    pub state: State,
+    /// Model indices of the rows currently shown in the table
+    pub visible: Vec<usize>,
+    /// Changes not written yet because autosave is off
+    pub unsaved: bool,
+    pub wind: Window,
+    /// Text pasted or dropped on the window, waiting for `Message::PasteBatch`
+    pub pasted: Rc<RefCell<String>>,
 }
 
 impl TodolistRS {
-    pub fn new() -> Self {
+    pub fn new(config: Config, files: Files, instance: Instance) -> Self {
         let a = App::default().with_scheme(Scheme::Gtk);
 
-        let widget_theme = WidgetTheme::new(ThemeType::Dark);
-        widget_theme.apply();
+        // Before the widgets are created, so they pick up the font size and the language
+        let palette = config.apply();
+        set_language(&config.language);
 
-        let widget_scheme = WidgetScheme::new(fltk_theme::SchemeType::Aqua);
-        widget_scheme.apply();
-
-        let mut wind = Window::default().with_label("Todolist RS");
+        let mut wind = Window::default()
+            .with_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+            .with_label("Todolist RS");
 
         let binding = Asset::get("ferris-logo.png").unwrap();
         let icon = binding.data.as_ref();
         wind.set_icon(Some(image::PngImage::from_data(icon).unwrap()));
 
         let (s, r) = channel::<Message>();
+        listen(&instance, &s);
 
-        let mut m_window = draw_ui(s);
+        let keymap = Keymap::new(&config.shortcuts);
+        let mut m_window = draw_ui(s.clone(), &keymap, palette);
+        show_theme(&mut m_window.hint, &mut m_window.menu_bar, &config, palette);
+        let session = load_session(&config.data_path, files.sessions.as_deref());
 
-        wind.set_size(
-            m_window.description_input.x() + m_window.description_input.width() + WIDGET_PADDING,
-            m_window.create_button.y() + m_window.create_button.height() + WIDGET_PADDING,
-        );
+        wind.make_resizable(true);
+        wind.size_range(WINDOW_MIN_WIDTH, WINDOW_MIN_HEIGHT, 0, 0);
+        wind.resize_callback({
+            let mut m_window = m_window.clone();
+            move |_, _, _, width, _| fit_layout(&mut m_window, width)
+        });
 
-        s.send(Message::Filter);
+        if session.column_widths.len() == 3 && session.column_widths.iter().all(|&w| w > 0) {
+            m_window
+                .list_table
+                .set_column_widths(&session.column_widths);
+        }
 
         wind.end();
+        // Fall back to the default geometry if the window would land on a missing monitor
+        match session.window {
+            Some([x, y, w, h]) if is_on_screen([x, y, w, h]) => wind.resize(x, y, w, h),
+            _ => wind.set_size(WINDOW_WIDTH, WINDOW_HEIGHT),
+        }
         wind.show();
+        fit_layout(&mut m_window, wind.width());
 
         // set_focus(&wind);
 
@@ -69,10 +133,154 @@ impl TodolistRS {
             _ => false,
         });
 
-        Self { a, m_window, r, s }
+        // Notice the desktop switching between light and dark while following it
+        watch_system_theme({
+            let s = s.clone();
+            move || s.send(Message::SystemTheme)
+        });
+
+        app::add_timeout3(DATES_REFRESH, {
+            let s = s.clone();
+            move |handle| {
+                s.send(Message::RefreshDates);
+                app::repeat_timeout3(DATES_REFRESH, handle);
+            }
+        });
+
+        // Bindings win over the table's own keys, like the arrows.
+        // A right click selects the row under the pointer, then opens its menu.
+        // Clicking the completed cell toggles it, double-clicking the description edits it.
+        // Hovering must not take the focus from the form, as `Fl_Table` does
+        let list_table = m_window.list_table.clone();
+        m_window.list_table.handle({
+            let keymap = keymap.clone();
+            let s = s.clone();
+            move |_, event| match event {
+                Event::Enter => true,
+                Event::KeyDown => match keymap.message() {
+                    Some(message) => {
+                        s.send(message);
+                        true
+                    }
+                    None => false,
+                },
+                Event::Released if app::event_mouse_button() == MouseButton::Right => {
+                    s.send(Message::ContextMenuAtPointer);
+                    false
+                }
+                // Ctrl and Shift clicks extend the selection instead
+                Event::Released
+                    if app::event_mouse_button() == MouseButton::Left
+                        && !event_state().intersects(Shortcut::Ctrl | Shortcut::Shift) =>
+                {
+                    match list_table.cell_at_pointer() {
+                        Some((_, DESCRIPTION_COLUMN)) if app::event_clicks() => {
+                            s.send(Message::Edit)
+                        }
+                        Some((_, COMPLETED_COLUMN)) => s.send(Message::ToggleCompleted),
+                        _ => {}
+                    }
+                    false
+                }
+                _ => false,
+            }
+        });
+
+        let pasted = Rc::new(RefCell::new(String::new()));
+
+        // Pasting several lines in the description opens the batch preview
+        m_window.description_input.handle({
+            let pasted = pasted.clone();
+            let s = s.clone();
+            move |_, event| match event {
+                Event::Paste if app::event_text().trim().contains('\n') => {
+                    *pasted.borrow_mut() = app::event_text();
+                    s.send(Message::PasteBatch);
+                    true
+                }
+                _ => false,
+            }
+        });
+
+        // Key bindings, Ctrl+Y as an alternative to Redo,
+        // pasting and dropping text files anywhere in the window.
+        // Keys reach the window once the focused widget has ignored them
+        wind.handle({
+            let keymap = keymap.clone();
+            let mut description_input = m_window.description_input.clone();
+            let pasted = pasted.clone();
+            let s = s.clone();
+            let mut dropped = false;
+            move |w, event| match event {
+                Event::Shortcut => {
+                    let key = event_key();
+                    if let Some(message) = keymap.message() {
+                        s.send(message);
+                    } else if !event_state().contains(Shortcut::Ctrl) {
+                        return false;
+                    } else if key == Key::from_char('y') {
+                        s.send(Message::List(update::Message::Redo));
+                    } else if key == Key::from_char('v') {
+                        app::paste_text(w);
+                    } else {
+                        return false;
+                    }
+                    true
+                }
+                Event::DndEnter | Event::DndDrag => true,
+                Event::DndRelease => {
+                    dropped = true;
+                    true
+                }
+                Event::Paste if dropped => {
+                    *pasted.borrow_mut() = read_dropped_files(&app::event_text());
+                    dropped = false;
+                    s.send(Message::PasteBatch);
+                    true
+                }
+                Event::Paste if app::event_text().trim().contains('\n') => {
+                    *pasted.borrow_mut() = app::event_text();
+                    s.send(Message::PasteBatch);
+                    true
+                }
+                // A single line goes to the description, as if pasted there
+                Event::Paste if !description_input.readonly() => {
+                    description_input.take_focus().unwrap_or_default();
+                    description_input
+                        .insert(&app::event_text())
+                        .unwrap_or_default();
+                    true
+                }
+                _ => false,
+            }
+        });
+
+        let mut todolist = Self {
+            a,
+            config,
+            files,
+            instance,
+            keymap,
+            m_window,
+            palette,
+            r,
+            s,
+            pasted,
+            session,
+            state: State::default(),
+            visible: vec![],
+            unsaved: false,
+            wind,
+        };
+        restore_state(&mut todolist);
+        todolist
     }
 
-    pub fn run(&mut self) {
+    /// Carry out the commands of the command line, then handle events until the window closes
+    pub fn run(&mut self, commands: Vec<Command>) {
+        if !commands.is_empty() {
+            self.s.send(Message::Commands(commands));
+        }
         message_waiting_loop(self);
     }
 }
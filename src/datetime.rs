@@ -0,0 +1,171 @@
+use std::env;
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, Utc,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{tr, tr_args, tr_count};
+
+/// Pattern of the ISO 8601 display
+const ISO_8601_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// How the dates of the items are shown. They are stored in UTC either way
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateDisplay {
+    /// Order and clock of the region of the `LC_TIME` locale
+    #[default]
+    Locale,
+    Iso8601,
+    /// How long ago, like "3 hours ago"
+    Relative,
+    /// The strftime pattern of the preferences
+    Custom,
+}
+
+impl DateDisplay {
+    pub const ALL: [DateDisplay; 4] = [
+        DateDisplay::Locale,
+        DateDisplay::Iso8601,
+        DateDisplay::Relative,
+        DateDisplay::Custom,
+    ];
+
+    pub fn label(self) -> String {
+        tr(match self {
+            DateDisplay::Locale => "date-display-locale",
+            DateDisplay::Iso8601 => "date-display-iso-8601",
+            DateDisplay::Relative => "date-display-relative",
+            DateDisplay::Custom => "date-display-custom",
+        })
+    }
+}
+
+/// Turns stored dates into text, in the time zone the system is in when they are shown
+#[derive(Clone, Debug, PartialEq)]
+pub enum DateFormat {
+    Pattern(String),
+    Relative,
+}
+
+impl DateFormat {
+    /// Format of the display, `pattern` being the one of the custom display.
+    /// An invalid pattern, which chrono cannot format with, shows dates as the locale does
+    pub fn new(display: DateDisplay, pattern: &str) -> Self {
+        match display {
+            DateDisplay::Custom if is_valid_pattern(pattern) => Self::Pattern(pattern.to_string()),
+            DateDisplay::Locale | DateDisplay::Custom => {
+                Self::Pattern(locale_pattern(&locale("LC_TIME")).to_string())
+            }
+            DateDisplay::Iso8601 => Self::Pattern(ISO_8601_FORMAT.to_string()),
+            DateDisplay::Relative => Self::Relative,
+        }
+    }
+
+    pub fn format(&self, at: DateTime<Utc>) -> String {
+        self.format_at(at, Utc::now())
+    }
+
+    /// The date as seen at `now`
+    fn format_at(&self, at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        match self {
+            Self::Pattern(pattern) => at.with_timezone(&Local).format(pattern).to_string(),
+            Self::Relative => relative(at, now),
+        }
+    }
+}
+
+/// Whether every specifier of a strftime pattern is known to chrono
+pub fn is_valid_pattern(pattern: &str) -> bool {
+    StrftimeItems::new(pattern).all(|item| item != Item::Error)
+}
+
+/// Locale of a category, like `LC_TIME`: `LC_ALL` overrides it, and `LANG` is the fallback
+pub fn locale(category: &str) -> String {
+    ["LC_ALL", category, "LANG"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+}
+
+/// Date and time pattern usual in the region of a locale like `en_US.UTF-8`
+fn locale_pattern(locale: &str) -> &'static str {
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    let (language, region) = locale.split_once(['_', '-']).unwrap_or((locale, ""));
+    match (language, region) {
+        ("" | "C" | "POSIX", _) => ISO_8601_FORMAT,
+        (_, "US" | "PH") => "%m/%d/%Y %I:%M %p",
+        ("zh" | "ja" | "ko" | "hu" | "lt" | "sv", _) | (_, "CA") => ISO_8601_FORMAT,
+        ("de" | "cs" | "da" | "fi" | "nb" | "pl" | "ru" | "sk" | "tr" | "uk", _) => {
+            "%d.%m.%Y %H:%M"
+        }
+        ("nl", _) => "%d-%m-%Y %H:%M",
+        _ => "%d/%m/%Y %H:%M",
+    }
+}
+
+/// How long before or after `now` the date is, in its largest whole unit
+fn relative(at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let seconds = (now - at).num_seconds();
+    let elapsed = seconds.unsigned_abs();
+    let (count, unit) = match elapsed {
+        0..=59 => return tr("date-just-now"),
+        60..=3_599 => (elapsed / 60, "date-minutes"),
+        3_600..=86_399 => (elapsed / 3_600, "date-hours"),
+        86_400..=2_591_999 => (elapsed / 86_400, "date-days"),
+        2_592_000..=31_535_999 => (elapsed / 2_592_000, "date-months"),
+        _ => (elapsed / 31_536_000, "date-years"),
+    };
+    let time = tr_count(unit, count as usize);
+    match seconds < 0 {
+        true => tr_args("date-in", &[("time", &time)]),
+        false => tr_args("date-ago", &[("time", &time)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn relative_dates_use_the_largest_unit() {
+        let now = Utc::now();
+        let ago = |seconds| DateFormat::Relative.format_at(now - Duration::seconds(seconds), now);
+        assert_eq!(ago(30), "just now");
+        assert_eq!(ago(60), "1 minute ago");
+        assert_eq!(ago(3 * 3_600 + 59), "3 hours ago");
+        assert_eq!(ago(2 * 86_400), "2 days ago");
+        assert_eq!(ago(400 * 86_400), "1 year ago");
+        assert_eq!(ago(-7_200), "in 2 hours");
+    }
+
+    #[test]
+    fn invalid_patterns_fall_back_to_the_locale() {
+        assert!(is_valid_pattern("%d %B %Y, %H:%M"));
+        assert!(!is_valid_pattern("%Q"));
+        assert!(!is_valid_pattern("%Y-%"));
+        assert_eq!(
+            DateFormat::new(DateDisplay::Custom, "%Y"),
+            DateFormat::Pattern("%Y".to_string())
+        );
+        assert_eq!(
+            DateFormat::new(DateDisplay::Custom, "%Q"),
+            DateFormat::new(DateDisplay::Locale, "%Q")
+        );
+    }
+
+    #[test]
+    fn locales_pick_the_order_of_their_region() {
+        assert_eq!(locale_pattern("en_US.UTF-8"), "%m/%d/%Y %I:%M %p");
+        assert_eq!(locale_pattern("en_GB.UTF-8"), "%d/%m/%Y %H:%M");
+        assert_eq!(locale_pattern("de_DE@euro"), "%d.%m.%Y %H:%M");
+        assert_eq!(locale_pattern("sv_SE"), ISO_8601_FORMAT);
+        assert_eq!(locale_pattern("C"), ISO_8601_FORMAT);
+        assert_eq!(locale_pattern(""), ISO_8601_FORMAT);
+    }
+}
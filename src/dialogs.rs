@@ -0,0 +1,709 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    path::PathBuf,
+    rc::Rc,
+};
+
+use fltk::{
+    app,
+    browser::CheckBrowser,
+    button::{Button, CheckButton},
+    dialog::{self, FileDialogAction, FileDialogOptions, FileDialogType, NativeFileChooser},
+    enums::{Align, CallbackTrigger, Event, Font, FrameType, Key},
+    frame::Frame,
+    input::Input,
+    menu::Choice,
+    misc::Spinner,
+    prelude::{DisplayExt, GroupExt, InputExt, MenuExt, WidgetBase, WidgetExt, WindowExt},
+    text::{TextBuffer, TextDisplay, TextEditor},
+    window::Window,
+};
+use fltk_theme::widget_themes;
+
+use crate::{
+    batch::split_candidates,
+    config::Config,
+    constants::{WIDGET_HEIGHT, WIDGET_PADDING, WIDGET_WIDTH},
+    datetime::{is_valid_pattern, DateDisplay},
+    i18n::{room_for, tr, tr_args, LANGUAGES},
+    theme::{theme_label, theme_names, SCHEMES},
+};
+
+/// Ask the user for a file to read
+pub fn choose_open_file(title: &str, filter: &str) -> Option<PathBuf> {
+    let mut chooser = NativeFileChooser::new(FileDialogType::BrowseFile);
+    chooser.set_title(title);
+    chooser.set_filter(filter);
+
+    match chooser.try_show() {
+        Ok(FileDialogAction::Success) => Some(chooser.filename()),
+        _ => None,
+    }
+}
+
+/// Ask the user for a file to write to
+pub fn choose_save_file(title: &str, filter: &str, preset: &str) -> Option<PathBuf> {
+    let mut chooser = NativeFileChooser::new(FileDialogType::BrowseSaveFile);
+    chooser.set_title(title);
+    chooser.set_filter(filter);
+    chooser.set_preset_file(preset);
+    chooser.set_option(FileDialogOptions::SaveAsConfirm);
+
+    match chooser.try_show() {
+        Ok(FileDialogAction::Success) => Some(chooser.filename()),
+        _ => None,
+    }
+}
+
+/// Open a modal window with a multi-line editor to paste text into.
+/// Returns `None` if the user cancels
+pub fn paste_text_dialog(title: &str) -> Option<String> {
+    let mut wind = Window::default()
+        .with_size(WIDGET_WIDTH * 7, WIDGET_HEIGHT * 12)
+        .with_label(title)
+        .center_screen();
+
+    let buffer = TextBuffer::default();
+    let mut editor = TextEditor::default()
+        .with_pos(WIDGET_PADDING, WIDGET_PADDING)
+        .with_size(
+            wind.width() - WIDGET_PADDING * 2,
+            wind.height() - WIDGET_HEIGHT - WIDGET_PADDING * 3,
+        );
+    editor.set_buffer(buffer.clone());
+
+    run_dialog(&mut wind, &tr("button-import"), || buffer.text())
+}
+
+/// Preview the to-dos found in pasted or dropped text.
+/// Returns the descriptions the user kept, `None` if the user cancels
+pub fn batch_preview_dialog(text: &str) -> Option<Vec<String>> {
+    let mut wind = Window::default()
+        .with_size(WIDGET_WIDTH * 7, WIDGET_HEIGHT * 14)
+        .with_label(&tr("title-batch"))
+        .center_screen();
+
+    let mut trim_input = CheckButton::default()
+        .with_size(wind.width() - WIDGET_PADDING * 2, WIDGET_HEIGHT)
+        .with_pos(WIDGET_PADDING, WIDGET_PADDING)
+        .with_label(&tr("label-trim-bullets"));
+    trim_input.set_checked(true);
+
+    let mut candidates_browser = CheckBrowser::default()
+        .with_pos(
+            WIDGET_PADDING,
+            trim_input.y() + trim_input.height() + WIDGET_PADDING,
+        )
+        .with_size(
+            wind.width() - WIDGET_PADDING * 2,
+            wind.height() - WIDGET_HEIGHT * 2 - WIDGET_PADDING * 4,
+        );
+    // Index in the text of the line of each candidate
+    let mut lines = vec![];
+    for (line, candidate) in split_candidates(text, true) {
+        candidates_browser.add(&candidate, true);
+        lines.push(line);
+    }
+
+    trim_input.set_callback({
+        let text = text.to_string();
+        let mut candidates_browser = candidates_browser.clone();
+        move |trim_input| {
+            // Lines holding only a marker come and go with the trimming,
+            // so the selection is carried over by line of the text
+            let checked = lines
+                .iter()
+                .enumerate()
+                .map(|(i, &line)| (line, candidates_browser.checked(i as i32 + 1)))
+                .collect::<HashMap<usize, bool>>();
+            candidates_browser.clear();
+            lines.clear();
+            for (line, candidate) in split_candidates(&text, trim_input.is_checked()) {
+                candidates_browser.add(&candidate, checked.get(&line).copied().unwrap_or(true));
+                lines.push(line);
+            }
+        }
+    });
+
+    run_dialog(&mut wind, &tr("button-create"), || {
+        (1..=candidates_browser.nitems() as i32)
+            .filter(|&line| candidates_browser.checked(line))
+            .filter_map(|line| candidates_browser.text(line))
+            .collect()
+    })
+}
+
+/// What to do with the trash, as picked in the trash dialog
+pub enum TrashAction {
+    /// Put back the items at these indices of the trash
+    Restore(Vec<usize>),
+    Empty,
+}
+
+/// List the trash, one line per item, to restore the checked ones or empty it.
+/// Returns `None` if the user cancels
+pub fn trash_dialog(lines: &[String]) -> Option<TrashAction> {
+    let mut wind = Window::default()
+        .with_size(WIDGET_WIDTH * 7, WIDGET_HEIGHT * 14)
+        .with_label(&tr("title-trash"))
+        .center_screen();
+
+    let mut all_input = CheckButton::default()
+        .with_size(wind.width() - WIDGET_PADDING * 2, WIDGET_HEIGHT)
+        .with_pos(WIDGET_PADDING, WIDGET_PADDING)
+        .with_label(&tr("label-check-all"));
+
+    let mut trash_browser = CheckBrowser::default()
+        .with_pos(
+            WIDGET_PADDING,
+            all_input.y() + all_input.height() + WIDGET_PADDING,
+        )
+        .with_size(
+            wind.width() - WIDGET_PADDING * 2,
+            wind.height() - WIDGET_HEIGHT * 2 - WIDGET_PADDING * 4,
+        );
+    for line in lines {
+        trash_browser.add(line, false);
+    }
+    all_input.set_callback({
+        let mut trash_browser = trash_browser.clone();
+        move |all_input| match all_input.is_checked() {
+            true => trash_browser.check_all(),
+            false => trash_browser.check_none(),
+        }
+    });
+
+    let emptied = Rc::new(Cell::new(false));
+    let empty_text = tr("button-empty-trash-ellipsis");
+    let mut empty_button = Button::default()
+        .with_size(
+            room_for(&[&empty_text], WIDGET_WIDTH + WIDGET_PADDING * 2),
+            WIDGET_HEIGHT,
+        )
+        .with_pos(
+            WIDGET_PADDING,
+            wind.height() - WIDGET_HEIGHT - WIDGET_PADDING,
+        )
+        .with_label(&empty_text);
+    empty_button.set_frame(widget_themes::OS_MINI_BUTTON_UP_BOX);
+    if lines.is_empty() {
+        empty_button.deactivate();
+    }
+    empty_button.set_callback({
+        let mut wind = wind.clone();
+        let emptied = emptied.clone();
+        move |_| {
+            emptied.set(true);
+            wind.hide();
+        }
+    });
+
+    run_dialog(&mut wind, &tr("button-restore"), || {
+        let checked = (1..=trash_browser.nitems() as i32)
+            .filter(|&line| trash_browser.checked(line))
+            .map(|line| line as usize - 1)
+            .collect();
+        TrashAction::Restore(checked)
+    })
+    .or_else(|| emptied.get().then_some(TrashAction::Empty))
+}
+
+/// List the archive, one line per item, with a search field,
+/// to put back the checked items. Returns their indices, `None` if the user cancels
+pub fn archive_dialog(lines: &[String]) -> Option<Vec<usize>> {
+    let mut wind = Window::default()
+        .with_size(WIDGET_WIDTH * 7, WIDGET_HEIGHT * 14)
+        .with_label(&tr("title-archive"))
+        .center_screen();
+
+    let search_text = tr("label-search");
+    let mut search_input = Input::default()
+        .with_size(WIDGET_WIDTH * 4, WIDGET_HEIGHT)
+        .with_pos(room_for(&[&search_text], WIDGET_WIDTH * 2), WIDGET_PADDING)
+        .with_label(&search_text);
+    search_input.set_trigger(CallbackTrigger::Changed);
+
+    let mut archive_browser = CheckBrowser::default()
+        .with_pos(
+            WIDGET_PADDING,
+            search_input.y() + search_input.height() + WIDGET_PADDING,
+        )
+        .with_size(
+            wind.width() - WIDGET_PADDING * 2,
+            wind.height() - WIDGET_HEIGHT * 2 - WIDGET_PADDING * 4,
+        );
+    for line in lines {
+        archive_browser.add(line, false);
+    }
+
+    // Indices of the lines shown, and whether each line is checked, shown or not
+    let shown = Rc::new(RefCell::new((0..lines.len()).collect::<Vec<usize>>()));
+    let checked = Rc::new(RefCell::new(vec![false; lines.len()]));
+    let remember_checks = {
+        let archive_browser = archive_browser.clone();
+        let shown = shown.clone();
+        let checked = checked.clone();
+        move || {
+            for (line, &index) in shown.borrow().iter().enumerate() {
+                checked.borrow_mut()[index] = archive_browser.checked(line as i32 + 1);
+            }
+        }
+    };
+    search_input.set_callback({
+        let lines = lines.to_vec();
+        let mut archive_browser = archive_browser.clone();
+        let shown = shown.clone();
+        let checked = checked.clone();
+        let remember_checks = remember_checks.clone();
+        move |search_input| {
+            remember_checks();
+            let search = search_input.value().to_lowercase();
+            *shown.borrow_mut() = (0..lines.len())
+                .filter(|&index| lines[index].to_lowercase().contains(&search))
+                .collect();
+            archive_browser.clear();
+            for &index in shown.borrow().iter() {
+                archive_browser.add(&lines[index], checked.borrow()[index]);
+            }
+        }
+    });
+
+    run_dialog(&mut wind, &tr("button-unarchive"), || {
+        remember_checks();
+        let checked = checked.borrow();
+        (0..lines.len()).filter(|&index| checked[index]).collect()
+    })
+}
+
+/// Let the user pick one of the options from a drop-down list.
+/// Returns the index of the option, `None` if the user cancels
+pub fn choose_option(title: &str, label: &str, options: &[String]) -> Option<usize> {
+    let field_x = room_for(&[label], WIDGET_WIDTH * 2);
+    let mut wind = Window::default()
+        .with_size(
+            field_x + WIDGET_WIDTH * 3,
+            WIDGET_HEIGHT * 2 + WIDGET_PADDING * 3,
+        )
+        .with_label(title)
+        .center_screen();
+
+    let mut choice = Choice::default()
+        .with_size(WIDGET_WIDTH * 2, WIDGET_HEIGHT)
+        .with_pos(field_x, WIDGET_PADDING)
+        .with_label(label);
+    for option in options {
+        choice.add_choice(option);
+    }
+    choice.set_value(0);
+
+    run_dialog(&mut wind, &tr("button-ok"), || {
+        usize::try_from(choice.value()).ok()
+    })
+    .flatten()
+}
+
+/// Edit the preferences.
+/// Returns the new preferences, `None` if the user cancels
+pub fn preferences_dialog(config: &Config) -> Option<Config> {
+    let labels = [
+        "pref-theme",
+        "pref-scheme",
+        "pref-date-display",
+        "pref-custom-format",
+        "pref-data-file",
+        "pref-font-size",
+        "pref-trash-days",
+        "pref-archive-days",
+        "pref-language",
+    ]
+    .map(tr);
+    let [theme_text, scheme_text, date_display_text, custom_format_text, data_file_text, font_size_text, trash_days_text, archive_days_text, language_text] =
+        &labels;
+    // Left of the fields, with room for the widest label
+    let field_x = room_for(&labels.each_ref().map(String::as_str), WIDGET_WIDTH * 2);
+    let mut wind = Window::default()
+        .with_size(
+            field_x + WIDGET_WIDTH * 5,
+            WIDGET_HEIGHT * 12 + WIDGET_PADDING * 13,
+        )
+        .with_label(&tr("title-preferences"))
+        .center_screen();
+    // Top of the n-th row of fields
+    let row = |n: i32| WIDGET_PADDING + (WIDGET_HEIGHT + WIDGET_PADDING) * n;
+    let check_width = wind.width() - field_x - WIDGET_PADDING;
+
+    let mut theme_choice = Choice::default()
+        .with_size(WIDGET_WIDTH * 3, WIDGET_HEIGHT)
+        .with_pos(field_x, row(0))
+        .with_label(theme_text);
+    for name in theme_names() {
+        theme_choice.add_choice(&theme_label(name));
+    }
+    theme_choice.set_value(
+        theme_names()
+            .position(|name| name == config.theme)
+            .map_or(0, |index| index as i32),
+    );
+
+    let mut scheme_choice = Choice::default()
+        .with_size(WIDGET_WIDTH * 3, WIDGET_HEIGHT)
+        .with_pos(field_x, row(1))
+        .with_label(scheme_text);
+    for (name, _) in SCHEMES {
+        scheme_choice.add_choice(name);
+    }
+    scheme_choice.set_value(
+        SCHEMES
+            .iter()
+            .position(|(name, _)| *name == config.scheme)
+            .map_or(0, |index| index as i32),
+    );
+
+    let mut date_display_choice = Choice::default()
+        .with_size(WIDGET_WIDTH * 3, WIDGET_HEIGHT)
+        .with_pos(field_x, row(2))
+        .with_label(date_display_text);
+    for display in DateDisplay::ALL {
+        date_display_choice.add_choice(&display.label());
+    }
+    date_display_choice.set_value(
+        DateDisplay::ALL
+            .iter()
+            .position(|&display| display == config.date_display)
+            .map_or(0, |index| index as i32),
+    );
+
+    let mut datetime_format_input = Input::default()
+        .with_size(WIDGET_WIDTH * 3, WIDGET_HEIGHT)
+        .with_pos(field_x, row(3))
+        .with_label(custom_format_text);
+    datetime_format_input.set_value(&config.datetime_format);
+    datetime_format_input.set_tooltip(&tr("tooltip-custom-format"));
+    // The pattern only applies to the custom display
+    let is_custom =
+        |choice: &Choice| DateDisplay::ALL[choice.value().max(0) as usize] == DateDisplay::Custom;
+    if !is_custom(&date_display_choice) {
+        datetime_format_input.deactivate();
+    }
+    date_display_choice.set_callback({
+        let mut datetime_format_input = datetime_format_input.clone();
+        move |choice| match is_custom(choice) {
+            true => datetime_format_input.activate(),
+            false => datetime_format_input.deactivate(),
+        }
+    });
+
+    let mut data_path_input = Input::default()
+        .with_size(WIDGET_WIDTH * 3, WIDGET_HEIGHT)
+        .with_pos(field_x, row(4))
+        .with_label(data_file_text);
+    data_path_input.set_value(&config.data_path);
+
+    let browse_text = tr("button-browse");
+    let mut browse_button = Button::default()
+        .with_size(
+            room_for(&[&browse_text], WIDGET_WIDTH + WIDGET_PADDING * 2),
+            WIDGET_HEIGHT,
+        )
+        .right_of(&data_path_input, WIDGET_PADDING)
+        .with_label(&browse_text);
+    browse_button.set_frame(widget_themes::OS_MINI_BUTTON_UP_BOX);
+    browse_button.set_callback({
+        let mut data_path_input = data_path_input.clone();
+        move |_| {
+            if let Some(path) = choose_save_file(&tr("title-data-file"), "*.dat", ".todo.dat") {
+                data_path_input.set_value(&path.to_string_lossy());
+            }
+        }
+    });
+
+    let mut font_size_input = Spinner::default()
+        .with_size(WIDGET_WIDTH, WIDGET_HEIGHT)
+        .with_pos(field_x, row(5))
+        .with_label(font_size_text);
+    font_size_input.set_range(8.0, 20.0);
+    font_size_input.set_step(1.0);
+    font_size_input.set_value(config.font_size as f64);
+
+    let confirm_delete_input = CheckButton::default()
+        .with_size(check_width, WIDGET_HEIGHT)
+        .with_pos(field_x, row(6))
+        .with_label(&tr("pref-confirm-delete"));
+    confirm_delete_input.set_checked(config.confirm_delete);
+
+    let mut autosave_input = CheckButton::default()
+        .with_size(check_width, WIDGET_HEIGHT)
+        .with_pos(field_x, row(7))
+        .with_label(&tr("pref-autosave"));
+    autosave_input.set_checked(config.autosave);
+    autosave_input.set_tooltip(&tr("tooltip-autosave"));
+
+    let mut trash_days_input = Spinner::default()
+        .with_size(WIDGET_WIDTH, WIDGET_HEIGHT)
+        .with_pos(field_x, row(8))
+        .with_label(trash_days_text);
+    trash_days_input.set_range(0.0, 3650.0);
+    trash_days_input.set_step(1.0);
+    trash_days_input.set_value(config.trash_days as f64);
+    trash_days_input.set_tooltip(&tr("tooltip-trash-days"));
+
+    let mut archive_days_input = Spinner::default()
+        .with_size(WIDGET_WIDTH, WIDGET_HEIGHT)
+        .with_pos(field_x, row(9))
+        .with_label(archive_days_text);
+    archive_days_input.set_range(0.0, 3650.0);
+    archive_days_input.set_step(1.0);
+    archive_days_input.set_value(config.archive_days as f64);
+    archive_days_input.set_tooltip(&tr("tooltip-archive-days"));
+
+    // The system default first, then the languages with a catalog
+    let mut language_choice = Choice::default()
+        .with_size(WIDGET_WIDTH * 3, WIDGET_HEIGHT)
+        .with_pos(field_x, row(10))
+        .with_label(language_text);
+    language_choice.add_choice(&tr("language-system"));
+    for (_, name) in LANGUAGES {
+        language_choice.add_choice(name);
+    }
+    language_choice.set_value(
+        LANGUAGES
+            .iter()
+            .position(|(code, _)| *code == config.language)
+            .map_or(0, |index| index as i32 + 1),
+    );
+    language_choice.set_tooltip(&tr("tooltip-language"));
+
+    run_dialog(&mut wind, &tr("button-save"), || {
+        let theme = theme_names()
+            .nth(theme_choice.value().max(0) as usize)
+            .unwrap_or_default();
+        let (scheme, _) = SCHEMES[scheme_choice.value().max(0) as usize];
+        let defaults = Config::default();
+        Config {
+            theme: theme.to_string(),
+            scheme: scheme.to_string(),
+            date_display: DateDisplay::ALL[date_display_choice.value().max(0) as usize],
+            datetime_format: match datetime_format_input.value().trim() {
+                "" => defaults.datetime_format,
+                format if !is_valid_pattern(format) => {
+                    dialog::alert_default(&tr_args("alert-date-format", &[("format", &format)]));
+                    config.datetime_format.clone()
+                }
+                format => format.to_string(),
+            },
+            data_path: match data_path_input.value().trim() {
+                "" => defaults.data_path,
+                path => path.to_string(),
+            },
+            font_size: font_size_input.value() as i32,
+            confirm_delete: confirm_delete_input.is_checked(),
+            autosave: autosave_input.is_checked(),
+            trash_days: trash_days_input.value() as u32,
+            archive_days: archive_days_input.value() as u32,
+            language: match language_choice.value() {
+                index @ 1.. => LANGUAGES[index as usize - 1].0.to_string(),
+                _ => String::new(),
+            },
+            shortcuts: config.shortcuts.clone(),
+        }
+    })
+}
+
+/// Edit a text in place, with an input laid over an area of the window.
+/// Enter confirms, Escape or clicking elsewhere cancels.
+/// Returns the new text, `None` if the user cancels
+pub fn inline_edit(
+    wind: &mut Window,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+    text: &str,
+) -> Option<String> {
+    wind.begin();
+    let mut input = Input::default().with_pos(x, y).with_size(w, h);
+    wind.end();
+    input.set_value(text);
+    input.set_position(0).unwrap_or_default();
+    input.set_mark(text.len() as i32).unwrap_or_default();
+    input.set_trigger(CallbackTrigger::EnterKeyAlways);
+
+    // `Some(true)` once confirmed, `Some(false)` once cancelled
+    let done = Rc::new(Cell::new(None));
+    input.set_callback({
+        let done = done.clone();
+        move |_| done.set(Some(true))
+    });
+    input.handle({
+        let done = done.clone();
+        move |_, event| match event {
+            Event::KeyDown if app::event_key() == Key::Escape => {
+                done.set(Some(false));
+                true
+            }
+            Event::Unfocus => {
+                done.set(done.get().or(Some(false)));
+                false
+            }
+            _ => false,
+        }
+    });
+    input.take_focus().unwrap_or_default();
+    wind.redraw();
+
+    while done.get().is_none() && wind.shown() {
+        app::wait();
+    }
+
+    let value = input.value();
+    Input::delete(input);
+    wind.redraw();
+    (done.get() == Some(true)).then_some(value)
+}
+
+/// Show read-only text in a modal window until the user closes it
+pub fn text_dialog(title: &str, text: &str) {
+    let mut wind = Window::default()
+        .with_size(WIDGET_WIDTH * 7, WIDGET_HEIGHT * 12)
+        .with_label(title)
+        .center_screen();
+
+    let mut buffer = TextBuffer::default();
+    buffer.set_text(text);
+    let mut display = TextDisplay::default()
+        .with_pos(WIDGET_PADDING, WIDGET_PADDING)
+        .with_size(
+            wind.width() - WIDGET_PADDING * 2,
+            wind.height() - WIDGET_HEIGHT - WIDGET_PADDING * 3,
+        );
+    display.set_buffer(buffer);
+
+    let close_text = tr("button-close");
+    let close_width = room_for(&[&close_text], WIDGET_WIDTH);
+    let mut close_button = Button::default()
+        .with_size(close_width, WIDGET_HEIGHT)
+        .with_pos(
+            wind.width() - close_width - WIDGET_PADDING,
+            wind.height() - WIDGET_HEIGHT - WIDGET_PADDING,
+        )
+        .with_label(&close_text);
+    close_button.set_frame(widget_themes::OS_MINI_BUTTON_UP_BOX);
+    close_button.set_callback({
+        let mut wind = wind.clone();
+        move |_| wind.hide()
+    });
+
+    wind.end();
+    wind.make_modal(true);
+    wind.show();
+
+    while wind.shown() {
+        app::wait();
+    }
+    Window::delete(wind);
+}
+
+/// Show the key bindings over the main window until a key is pressed or the user clicks
+pub fn shortcuts_overlay(parent: &Window, bindings: &[(String, String)]) {
+    let line_height = WIDGET_HEIGHT - WIDGET_PADDING / 2;
+    let keys_width = WIDGET_WIDTH * 2;
+    let descriptions = bindings
+        .iter()
+        .map(|(_, description)| description.as_str())
+        .collect::<Vec<&str>>();
+    let width = keys_width + room_for(&descriptions, WIDGET_WIDTH * 4);
+    let height = line_height * (bindings.len() as i32 + 1) + WIDGET_PADDING * 2;
+    let mut wind = Window::default().with_size(width, height).with_pos(
+        parent.x() + (parent.width() - width) / 2,
+        parent.y() + (parent.height() - height) / 2,
+    );
+    wind.set_border(false);
+    wind.set_frame(FrameType::BorderBox);
+
+    let mut title = Frame::default()
+        .with_pos(WIDGET_PADDING, WIDGET_PADDING)
+        .with_size(width - WIDGET_PADDING * 2, line_height)
+        .with_label(&tr("title-shortcuts"))
+        .with_align(Align::Left | Align::Inside);
+    title.set_label_font(Font::HelveticaBold);
+
+    for (i, (key, description)) in bindings.iter().enumerate() {
+        let y = WIDGET_PADDING + line_height * (i as i32 + 1);
+        Frame::default()
+            .with_pos(WIDGET_PADDING, y)
+            .with_size(keys_width - WIDGET_PADDING, line_height)
+            .with_label(key)
+            .with_align(Align::Right | Align::Inside);
+        Frame::default()
+            .with_pos(keys_width + WIDGET_PADDING, y)
+            .with_size(width - keys_width - WIDGET_PADDING * 2, line_height)
+            .with_label(description)
+            .with_align(Align::Left | Align::Inside);
+    }
+
+    wind.end();
+    wind.make_modal(true);
+    wind.handle(|wind, event| match event {
+        Event::KeyDown | Event::Push => {
+            wind.hide();
+            true
+        }
+        _ => false,
+    });
+    wind.show();
+
+    while wind.shown() {
+        app::wait();
+    }
+    Window::delete(wind);
+}
+
+/// Add Cancel and OK buttons at the bottom right of a dialog window and show it modally.
+/// If the user accepts, returns what `accepted` reads from the widgets before the window,
+/// with its widgets, is deleted
+fn run_dialog<T>(wind: &mut Window, ok_label: &str, accepted: impl FnOnce() -> T) -> Option<T> {
+    let cancel_label = tr("button-cancel");
+    let button_width = room_for(&[&cancel_label, ok_label], WIDGET_WIDTH);
+    let mut cancel_button = Button::default()
+        .with_size(button_width, WIDGET_HEIGHT)
+        .with_pos(
+            wind.width() - (button_width + WIDGET_PADDING) * 2,
+            wind.height() - WIDGET_HEIGHT - WIDGET_PADDING,
+        )
+        .with_label(&cancel_label);
+    cancel_button.set_frame(widget_themes::OS_MINI_BUTTON_UP_BOX);
+
+    let mut ok_button = Button::default()
+        .with_size(button_width, WIDGET_HEIGHT)
+        .right_of(&cancel_button, WIDGET_PADDING)
+        .with_label(ok_label);
+    ok_button.set_frame(widget_themes::OS_MINI_BUTTON_UP_BOX);
+
+    wind.end();
+    wind.make_modal(true);
+    wind.show();
+
+    let ok = Rc::new(Cell::new(false));
+
+    cancel_button.set_callback({
+        let mut wind = wind.clone();
+        move |_| wind.hide()
+    });
+    ok_button.set_callback({
+        let mut wind = wind.clone();
+        let ok = ok.clone();
+        move |_| {
+            ok.set(true);
+            wind.hide();
+        }
+    });
+
+    while wind.shown() {
+        app::wait();
+    }
+
+    let result = ok.get().then(accepted);
+    Window::delete(wind.clone());
+    result
+}
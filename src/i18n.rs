@@ -0,0 +1,194 @@
+//! Translations of the user interface, from the Fluent catalogs of `locales/`.
+//! Messages depending on a number select their plural form by `$count`, see [`tr_count`]
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    sync::{OnceLock, PoisonError, RwLock},
+};
+
+use fltk::{app, draw, enums::Font};
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+use crate::{constants::WIDGET_PADDING, datetime::locale};
+
+/// Languages with a catalog: code, and name in the language itself
+pub const LANGUAGES: [(&str, &str); 2] = [("en", "English"), ("es", "Español")];
+
+/// Catalogs by language code. English has every message
+const CATALOGS: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+];
+
+/// Language of the messages missing from the catalog of the current one
+const FALLBACK: &str = "en";
+
+static LANGUAGE: RwLock<&str> = RwLock::new(FALLBACK);
+
+/// Bundle of each catalog, by language code
+fn bundles() -> &'static BTreeMap<&'static str, FluentBundle<FluentResource>> {
+    static BUNDLES: OnceLock<BTreeMap<&str, FluentBundle<FluentResource>>> = OnceLock::new();
+    BUNDLES.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|&(code, source)| (code, bundle(code, source)))
+            .collect()
+    })
+}
+
+/// Messages of a catalog. Those Fluent cannot read are left out, which the tests catch
+fn bundle(code: &str, source: &str) -> FluentBundle<FluentResource> {
+    let language = code.parse::<LanguageIdentifier>().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![language]);
+    // Labels would show the marks isolating the direction of the placeables
+    bundle.set_use_isolating(false);
+    let resource =
+        FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, _)| resource);
+    bundle.add_resource(resource).unwrap_or_default();
+    bundle
+}
+
+/// Show the interface in the language of a code like `es` or `es_ES.UTF-8`,
+/// or of the `LC_MESSAGES` locale if it is empty. Widgets created before keep their labels
+pub fn set_language(code: &str) {
+    let code = match code {
+        "" => locale("LC_MESSAGES"),
+        code => code.to_string(),
+    };
+    *LANGUAGE.write().unwrap_or_else(PoisonError::into_inner) = language_of(&code);
+}
+
+/// Language with a catalog matching the locale, English if there is none
+fn language_of(locale: &str) -> &'static str {
+    let language = locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default();
+    LANGUAGES
+        .iter()
+        .map(|&(code, _)| code)
+        .find(|code| code.eq_ignore_ascii_case(language))
+        .unwrap_or(FALLBACK)
+}
+
+/// Text of the message in the current language
+pub fn tr(id: &str) -> String {
+    tr_args(id, &[])
+}
+
+/// Text of the message with its `{ $name }` variables replaced by the values.
+/// Unknown messages show their id
+pub fn tr_args(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    let args = args
+        .iter()
+        .map(|&(name, value)| (name, value.to_string()))
+        .collect();
+    format_message(current_language(), id, &args)
+}
+
+/// Text of the message with the count as the `$count` variable,
+/// which its selector reads to pick the plural form
+pub fn tr_count(id: &str, count: usize) -> String {
+    let args = FluentArgs::from_iter([("count", count)]);
+    format_message(current_language(), id, &args)
+}
+
+fn current_language() -> &'static str {
+    *LANGUAGE.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Text of the message in the language, or in English if it has no translation
+fn format_message(language: &str, id: &str, args: &FluentArgs) -> String {
+    let bundles = bundles();
+    [language, FALLBACK]
+        .iter()
+        .find_map(|code| {
+            let bundle = bundles.get(code)?;
+            let pattern = bundle.get_message(id)?.value()?;
+            let mut errors = vec![];
+            Some(
+                bundle
+                    .format_pattern(pattern, Some(args), &mut errors)
+                    .into_owned(),
+            )
+        })
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// The width, or more if the widest of the texts needs it to fit in a label or button
+/// at the default font size. Layouts use it to make room for the longer labels of some languages
+pub fn room_for(texts: &[&str], width: i32) -> i32 {
+    draw::set_font(Font::Helvetica, app::font_size());
+    texts
+        .iter()
+        .map(|text| draw::measure(text, false).0 + WIDGET_PADDING * 2)
+        .fold(width, i32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use fluent_syntax::{ast::Entry, parser::parse_runtime};
+
+    use super::*;
+
+    /// Ids of the messages of a catalog, which must be valid Fluent
+    fn message_ids(source: &str) -> Vec<&str> {
+        let resource = FluentResource::try_new(source.to_string());
+        assert!(
+            resource.is_ok(),
+            "{:?}",
+            resource.err().map(|(_, errors)| errors)
+        );
+        let ids = parse_runtime(source).unwrap().body.into_iter();
+        let mut ids = ids
+            .filter_map(|entry| match entry {
+                Entry::Message(message) => Some(message.id.name),
+                _ => None,
+            })
+            .collect::<Vec<&str>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn every_message_is_translated() {
+        let english = message_ids(CATALOGS[0].1);
+        for (_, source) in CATALOGS {
+            assert_eq!(message_ids(source), english);
+        }
+    }
+
+    #[test]
+    fn counts_select_the_plural_form() {
+        let count = |language, id, count: usize| {
+            format_message(language, id, &FluentArgs::from_iter([("count", count)]))
+        };
+        assert_eq!(count("en", "date-days", 1), "1 day");
+        assert_eq!(count("en", "date-days", 2), "2 days");
+        assert_eq!(count("es", "date-days", 1), "1 día");
+        assert_eq!(count("es", "date-days", 0), "0 días");
+    }
+
+    #[test]
+    fn variables_are_replaced() {
+        let args = FluentArgs::from_iter([("path", "todo.dat")]);
+        assert_eq!(
+            format_message("en", "alert-data-file-open", &args),
+            "todo.dat is open in another window"
+        );
+        assert_eq!(
+            format_message("es", "unknown-message", &args),
+            "unknown-message"
+        );
+    }
+
+    #[test]
+    fn locales_pick_their_language() {
+        assert_eq!(language_of("es_ES.UTF-8"), "es");
+        assert_eq!(language_of("ES"), "es");
+        assert_eq!(language_of("fr_FR"), "en");
+        assert_eq!(language_of(""), "en");
+    }
+}
@@ -0,0 +1,241 @@
+//! One window per data file. The first launch locks the data file and listens on a socket
+//! next to it, on a thread of its own; later launches hand their arguments over the socket and quit.
+//! The socket is a Unix socket where there are some, and elsewhere a file holding the port
+//! of a loopback TCP socket
+
+use std::{
+    fs::{self, File, TryLockError},
+    io::{self, Read},
+    net::Shutdown,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+#[cfg(not(unix))]
+use std::net::{Ipv4Addr, TcpListener as Listener, TcpStream as Stream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener as Listener, UnixStream as Stream};
+
+use resolve_path::PathResolveExt;
+
+/// Usage printed when the arguments are not understood
+pub const USAGE: &str = "Usage: rust-fltk-todolist [add <description>]";
+
+/// Attempts to reach the instance holding the lock, which may still be starting
+const CONNECT_ATTEMPTS: u32 = 20;
+const CONNECT_DELAY: Duration = Duration::from_millis(100);
+/// Time given to a later launch to send its arguments
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What a launch asks of the window, from its command line
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Create a to-do with this description
+    Add(String),
+}
+
+/// Commands of the arguments, without the program name
+pub fn parse_args(args: &[String]) -> Result<Vec<Command>, String> {
+    match args {
+        [] => Ok(vec![]),
+        [command, words @ ..] if command == "add" => match words.join(" ").trim() {
+            "" => Err("Nothing to add".to_string()),
+            description => Ok(vec![Command::Add(description.to_string())]),
+        },
+        [command, ..] => Err(format!("Unknown command: {command}")),
+    }
+}
+
+/// Lock file of a data file: the file with `.lock` appended to its name
+fn lock_path(data_path: &str) -> String {
+    format!("{data_path}.lock")
+}
+
+/// Socket of a data file: the file with `.sock` appended to its name
+fn socket_path(data_path: &str) -> String {
+    format!("{data_path}.sock")
+}
+
+/// Hold on the data file of the running window, and the socket later launches write to
+pub struct Instance {
+    /// Locked as long as it is open
+    _lock: File,
+    /// `None` if the socket could not be created, leaving later launches unanswered
+    listener: Option<Listener>,
+    socket: PathBuf,
+    /// Tells the listening thread to stop, once the lock is released
+    stop: Arc<AtomicBool>,
+}
+
+impl Instance {
+    /// Lock the data file, or hand the arguments to the window that has it locked.
+    /// Returns `None` once they are handed over
+    pub fn acquire(data_path: &str, args: &[String]) -> io::Result<Option<Self>> {
+        let lock = File::create(lock_path(data_path).resolve())?;
+        let socket = socket_path(data_path).resolve().into_owned();
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                forward(&socket, args)?;
+                return Ok(None);
+            }
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+
+        // Left behind by a window that did not quit cleanly
+        match fs::remove_file(&socket) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => eprintln!("{err:?}"),
+            _ => {}
+        }
+        let listener = bind(&socket).map_err(|err| eprintln!("{err:?}")).ok();
+
+        Ok(Some(Self {
+            _lock: lock,
+            listener,
+            socket,
+            stop: Arc::default(),
+        }))
+    }
+
+    /// Hand the arguments of each later launch to `received`, from a thread reading the socket
+    /// until the lock is released
+    pub fn listen(&self, received: impl Fn(Vec<String>) + Send + 'static) {
+        let Some(listener) = self.listener.as_ref().and_then(|listener| {
+            listener
+                .try_clone()
+                .map_err(|err| eprintln!("{err:?}"))
+                .ok()
+        }) else {
+            return;
+        };
+        let stop = self.stop.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::Acquire) {
+                    break;
+                }
+                match stream.and_then(read_args) {
+                    Ok(args) => received(args),
+                    Err(err) => eprintln!("{err:?}"),
+                }
+            }
+        });
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        if self.listener.is_some() {
+            // Wake the listening thread up so that it sees it has to stop
+            self.stop.store(true, Ordering::Release);
+            connect(&self.socket).map(drop).unwrap_or_default();
+            fs::remove_file(&self.socket).unwrap_or_default();
+        }
+    }
+}
+
+#[cfg(unix)]
+fn bind(socket: &Path) -> io::Result<Listener> {
+    Listener::bind(socket)
+}
+
+/// Listen on a port picked by the system, written to the socket file
+#[cfg(not(unix))]
+fn bind(socket: &Path) -> io::Result<Listener> {
+    let listener = Listener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    fs::write(socket, listener.local_addr()?.port().to_string())?;
+    Ok(listener)
+}
+
+#[cfg(unix)]
+fn connect(socket: &Path) -> io::Result<Stream> {
+    Stream::connect(socket)
+}
+
+/// Connect to the port written to the socket file
+#[cfg(not(unix))]
+fn connect(socket: &Path) -> io::Result<Stream> {
+    let port = fs::read_to_string(socket)?
+        .trim()
+        .parse::<u16>()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    Stream::connect((Ipv4Addr::LOCALHOST, port))
+}
+
+/// Send the arguments to the instance listening on the socket, as a JSON array
+fn forward(socket: &Path, args: &[String]) -> io::Result<()> {
+    let mut attempts = 1;
+    let stream = loop {
+        match connect(socket) {
+            Ok(stream) => break stream,
+            Err(_) if attempts < CONNECT_ATTEMPTS => {
+                attempts += 1;
+                thread::sleep(CONNECT_DELAY);
+            }
+            Err(err) => return Err(err),
+        }
+    };
+    serde_json::to_writer(&stream, args)?;
+    stream.shutdown(Shutdown::Write)
+}
+
+/// Arguments sent by a later launch
+fn read_args(stream: Stream) -> io::Result<Vec<String>> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut text = String::new();
+    (&stream).read_to_string(&mut text)?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process, sync::mpsc};
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn arguments_are_read_as_commands() {
+        assert_eq!(parse_args(&[]), Ok(vec![]));
+        assert_eq!(
+            parse_args(&args(&["add", "buy milk"])),
+            Ok(vec![Command::Add("buy milk".to_string())])
+        );
+        assert_eq!(
+            parse_args(&args(&["add", "buy", "milk"])),
+            Ok(vec![Command::Add("buy milk".to_string())])
+        );
+        assert!(parse_args(&args(&["add", " "])).is_err());
+        assert!(parse_args(&args(&["remove", "milk"])).is_err());
+    }
+
+    #[test]
+    fn later_launches_hand_their_arguments_over() {
+        let data_path = env::temp_dir()
+            .join(format!("todolist-instance-{}.dat", process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let first = Instance::acquire(&data_path, &[]).unwrap().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        first.listen(move |args| sender.send(args).unwrap());
+
+        let sent = args(&["add", "buy milk"]);
+        assert!(Instance::acquire(&data_path, &sent).unwrap().is_none());
+        assert_eq!(receiver.recv_timeout(READ_TIMEOUT), Ok(sent));
+
+        // Released with the first window, which stops listening, and the socket removed
+        drop(first);
+        assert!(receiver.recv_timeout(READ_TIMEOUT).is_err());
+        assert!(Instance::acquire(&data_path, &[]).unwrap().is_some());
+        assert!(!PathBuf::from(socket_path(&data_path)).exists());
+        fs::remove_file(lock_path(&data_path)).unwrap_or_default();
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::BTreeMap;
+
+use fltk::{
+    app::{event_key, event_state, event_text},
+    draw,
+    enums::{Key, Shortcut},
+};
+
+use todolist_core::update;
+
+use crate::{i18n::tr, utils::Message};
+
+/// Actions that can be bound to a key: name in the `[shortcuts]` table
+/// of the config file, default binding, id of the description and message
+const ACTIONS: [(&str, &str, &str, Message); 22] = [
+    ("new-todo", "Ctrl+N", "shortcut-new-todo", Message::NewTodo),
+    ("create", "Enter", "shortcut-create", Message::Create),
+    (
+        "toggle-completed",
+        "Space",
+        "shortcut-toggle-completed",
+        Message::ToggleCompleted,
+    ),
+    ("edit", "F2", "shortcut-edit", Message::Edit),
+    (
+        "duplicate",
+        "Ctrl+D",
+        "shortcut-duplicate",
+        Message::Duplicate,
+    ),
+    ("copy", "Ctrl+C", "shortcut-copy", Message::CopyText),
+    ("history", "Ctrl+H", "shortcut-history", Message::History),
+    ("delete", "Delete", "shortcut-delete", Message::Delete),
+    (
+        "context-menu",
+        "Menu",
+        "shortcut-context-menu",
+        Message::ContextMenu,
+    ),
+    (
+        "context-menu-alt",
+        "Shift+F10",
+        "shortcut-context-menu",
+        Message::ContextMenu,
+    ),
+    (
+        "select-all",
+        "Ctrl+A",
+        "shortcut-select-all",
+        Message::List(update::Message::SelectAll),
+    ),
+    (
+        "previous",
+        "Up",
+        "shortcut-previous",
+        Message::List(update::Message::SelectPrevious),
+    ),
+    (
+        "next",
+        "Down",
+        "shortcut-next",
+        Message::List(update::Message::SelectNext),
+    ),
+    ("filter", "Ctrl+F", "shortcut-filter", Message::FocusFilter),
+    (
+        "undo",
+        "Ctrl+Z",
+        "shortcut-undo",
+        Message::List(update::Message::Undo),
+    ),
+    (
+        "redo",
+        "Ctrl+Shift+Z",
+        "shortcut-redo",
+        Message::List(update::Message::Redo),
+    ),
+    (
+        "new-data-file",
+        "Ctrl+Shift+N",
+        "shortcut-new-data-file",
+        Message::NewDataFile,
+    ),
+    (
+        "open-data-file",
+        "Ctrl+O",
+        "shortcut-open-data-file",
+        Message::OpenDataFile,
+    ),
+    ("print", "Ctrl+P", "shortcut-print", Message::PrintReport),
+    (
+        "preferences",
+        "Ctrl+,",
+        "shortcut-preferences",
+        Message::Preferences,
+    ),
+    ("shortcuts", "?", "shortcut-shortcuts", Message::Shortcuts),
+    ("quit", "Ctrl+Q", "shortcut-quit", Message::Quit),
+];
+
+const MODIFIERS: [(&str, Shortcut); 4] = [
+    ("ctrl", Shortcut::Ctrl),
+    ("shift", Shortcut::Shift),
+    ("alt", Shortcut::Alt),
+    ("meta", Shortcut::Meta),
+];
+
+const KEYS: [(&str, Key); 16] = [
+    ("enter", Key::Enter),
+    ("space", Key::from_char(' ')),
+    ("tab", Key::Tab),
+    ("escape", Key::Escape),
+    ("backspace", Key::BackSpace),
+    ("delete", Key::Delete),
+    ("insert", Key::Insert),
+    ("up", Key::Up),
+    ("down", Key::Down),
+    ("left", Key::Left),
+    ("right", Key::Right),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("pageup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+    ("menu", Key::Menu),
+];
+
+/// Key bindings of the main window
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: Vec<(Shortcut, &'static str, Message)>,
+}
+
+impl Keymap {
+    /// Default bindings with the overrides of the config file applied.
+    /// An empty or `none` binding disables the action
+    pub fn new(overrides: &BTreeMap<String, String>) -> Self {
+        for name in overrides.keys() {
+            if !ACTIONS.iter().any(|(action, ..)| action == name) {
+                eprintln!("Unknown shortcut action: {name}");
+            }
+        }
+
+        let bindings = ACTIONS
+            .iter()
+            .filter_map(|(name, default, description, message)| {
+                let shortcut = match overrides.get(*name) {
+                    Some(text) if text.is_empty() || text.eq_ignore_ascii_case("none") => {
+                        return None
+                    }
+                    Some(text) => parse_shortcut(text).or_else(|| {
+                        eprintln!("Invalid shortcut for {name}: {text}");
+                        parse_shortcut(default)
+                    }),
+                    None => parse_shortcut(default),
+                }?;
+                Some((shortcut, *description, message.clone()))
+            })
+            .collect();
+
+        Self { bindings }
+    }
+
+    /// Key bound to the message, `Shortcut::None` if there is none
+    pub fn shortcut(&self, message: &Message) -> Shortcut {
+        self.bindings
+            .iter()
+            .find(|(_, _, bound)| bound == message)
+            .map_or(Shortcut::None, |&(shortcut, ..)| shortcut)
+    }
+
+    /// Message bound to the key of the current event
+    pub fn message(&self) -> Option<Message> {
+        self.bindings
+            .iter()
+            .find(|&&(shortcut, ..)| matches_event(shortcut))
+            .map(|(_, _, message)| message.clone())
+    }
+
+    /// Key labels and descriptions of the bindings, in the order of `ACTIONS`.
+    /// Keys of the same description, like the two of the context menu, share a line
+    pub fn descriptions(&self) -> Vec<(String, String)> {
+        let mut lines: Vec<(String, &str)> = vec![];
+        for &(shortcut, description, _) in &self.bindings {
+            let label = draw::shortcut_label(shortcut);
+            match lines.iter_mut().find(|(_, line)| *line == description) {
+                Some((keys, _)) => *keys = format!("{keys} / {label}"),
+                None => lines.push((label, description)),
+            }
+        }
+        lines
+            .into_iter()
+            .map(|(keys, description)| (keys, tr(description)))
+            .collect()
+    }
+}
+
+/// Parse a binding like `Ctrl+Shift+Z`, `F2`, `Space` or `?`
+fn parse_shortcut(text: &str) -> Option<Shortcut> {
+    // `+` itself may be the key, as in `Ctrl++`
+    let (modifiers, key) = match text.strip_suffix("++") {
+        Some(modifiers) => (modifiers, "+"),
+        None => text.rsplit_once('+').unwrap_or(("", text)),
+    };
+
+    let mut shortcut = Shortcut::None;
+    for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+        let (_, modifier) = MODIFIERS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(modifier.trim()))?;
+        shortcut |= *modifier;
+    }
+
+    let key = key.trim();
+    let lowercase = key.to_lowercase();
+    let mut chars = key.chars();
+    let key = if let Some((_, key)) = KEYS.iter().find(|(name, _)| *name == lowercase) {
+        *key
+    } else if let Some(n) = lowercase
+        .strip_prefix('f')
+        .and_then(|n| n.parse::<i32>().ok())
+        .filter(|n| (1..=12).contains(n))
+    {
+        Key::from_i32(Key::F1.bits() + n - 1)
+    } else if let (Some(c), None) = (chars.next(), chars.next()) {
+        // Letters are reported lowercase, whatever the state of Shift
+        Key::from_char(c.to_ascii_lowercase())
+    } else {
+        return None;
+    };
+
+    Some(shortcut | key)
+}
+
+/// Whether the key event matches the binding.
+/// Punctuation is compared as typed, so Shift is not needed for `?`
+fn matches_event(shortcut: Shortcut) -> bool {
+    let modifiers = MODIFIERS
+        .iter()
+        .fold(Shortcut::None, |all, &(_, modifier)| all | modifier);
+    let state = event_state() & modifiers;
+    let wanted = shortcut & modifiers;
+    let key = shortcut.key();
+
+    match key.to_char() {
+        Some(c) if c.is_ascii_punctuation() => {
+            event_text() == c.to_string() && state - Shortcut::Shift == wanted - Shortcut::Shift
+        }
+        _ => {
+            let pressed = match event_key() {
+                pressed if pressed == Key::KPEnter => Key::Enter,
+                pressed => pressed,
+            };
+            pressed == key && state == wanted
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcuts_are_parsed() {
+        let ctrl = Shortcut::Ctrl;
+        assert_eq!(parse_shortcut("Ctrl+N"), Some(ctrl | 'n'));
+        assert_eq!(
+            parse_shortcut("ctrl + shift + z"),
+            Some(ctrl | Shortcut::Shift | 'z')
+        );
+        assert_eq!(parse_shortcut("Ctrl++"), Some(ctrl | '+'));
+        assert_eq!(parse_shortcut("Ctrl+,"), Some(ctrl | ','));
+        assert_eq!(parse_shortcut("F2"), Some(Shortcut::None | Key::F2));
+        assert_eq!(
+            parse_shortcut("f12"),
+            Some(Shortcut::None | Key::from_i32(Key::F1.bits() + 11))
+        );
+        assert_eq!(parse_shortcut("Space"), Some(Shortcut::None | ' '));
+        assert_eq!(
+            parse_shortcut("Shift+F10"),
+            Some(Shortcut::Shift | Key::from_i32(Key::F1.bits() + 9))
+        );
+        assert_eq!(parse_shortcut("?"), Some(Shortcut::None | '?'));
+    }
+
+    #[test]
+    fn invalid_shortcuts_are_rejected() {
+        for text in [
+            "",
+            "Ctrl+",
+            "Hyper+A",
+            "F13",
+            "F0",
+            "Enterprise",
+            "Ctrl+Shift",
+        ] {
+            assert_eq!(parse_shortcut(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn overrides_replace_the_defaults() {
+        let overrides = BTreeMap::from(
+            [
+                ("edit", "Ctrl+E"),
+                ("delete", "none"),
+                ("copy", ""),
+                ("undo", "Hyper+Z"),
+                ("unknown-action", "Ctrl+U"),
+            ]
+            .map(|(action, text)| (action.to_string(), text.to_string())),
+        );
+        let keymap = Keymap::new(&overrides);
+        assert_eq!(keymap.shortcut(&Message::Edit), Shortcut::Ctrl | 'e');
+        assert_eq!(keymap.shortcut(&Message::Delete), Shortcut::None);
+        assert_eq!(keymap.shortcut(&Message::CopyText), Shortcut::None);
+        // Invalid overrides keep the default
+        assert_eq!(
+            keymap.shortcut(&Message::List(update::Message::Undo)),
+            Shortcut::Ctrl | 'z'
+        );
+        assert_eq!(keymap.shortcut(&Message::Quit), Shortcut::Ctrl | 'q');
+    }
+
+    #[test]
+    fn keys_of_the_same_action_share_a_line() {
+        let menu = tr("shortcut-context-menu");
+        let lines_of = |keymap: &Keymap| {
+            let lines = keymap.descriptions().into_iter();
+            lines
+                .filter(|(_, description)| *description == menu)
+                .map(|(keys, _)| keys)
+                .collect::<Vec<String>>()
+        };
+        let both = lines_of(&Keymap::new(&BTreeMap::new()));
+        assert_eq!(both.len(), 1);
+        assert!(both[0].contains(" / "), "{}", both[0]);
+
+        let overrides = BTreeMap::from([("context-menu-alt".to_string(), "none".to_string())]);
+        let one = lines_of(&Keymap::new(&overrides));
+        assert_eq!(one.len(), 1);
+        assert!(!one[0].contains(" / "), "{}", one[0]);
+    }
+}
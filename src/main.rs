@@ -1,10 +1,45 @@
-use controller::TodolistRS;
+use std::{env, process};
 
+use config::load_config;
+use controller::{Files, TodolistRS};
+use instance::{parse_args, Instance, USAGE};
+
+mod batch;
+mod config;
 mod constants;
 mod controller;
-mod models;
+mod datetime;
+mod dialogs;
+mod i18n;
+mod instance;
+mod keymap;
+mod markdown;
+mod orgmode;
+mod report;
+mod session;
+mod table;
+mod taskwarrior;
+mod theme;
+#[cfg(test)]
+mod ui_tests;
 mod utils;
 
 fn main() {
-    TodolistRS::new().run();
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let commands = parse_args(&args).unwrap_or_else(|err| {
+        eprintln!("{err}\n{USAGE}");
+        process::exit(2);
+    });
+
+    // A window already open on the data file takes the arguments instead
+    let files = Files::standard();
+    let config = load_config(files.config.as_deref());
+    match Instance::acquire(&config.data_path, &args) {
+        Ok(Some(instance)) => TodolistRS::new(config, files, instance).run(commands),
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("{}: {err}", config.data_path);
+            process::exit(1);
+        }
+    }
 }
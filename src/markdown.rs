@@ -0,0 +1,117 @@
+use chrono::{DateTime, Utc};
+use todolist_core::ListItem;
+
+use crate::table::display_text;
+
+/// Render the items as a GitHub-style Markdown checklist, one line per item:
+/// line breaks in descriptions become spaces
+pub fn to_checklist<'a>(items: impl IntoIterator<Item = &'a ListItem>) -> String {
+    items
+        .into_iter()
+        .map(|item| {
+            format!(
+                "- [{}] {}\n",
+                match item.completed {
+                    true => "x",
+                    false => " ",
+                },
+                display_text(&item.description)
+            )
+        })
+        .collect()
+}
+
+/// Parse a Markdown checklist into to-do items created, and completed if checked,
+/// at `created_at`. Lines that are not checklist entries are skipped
+pub fn from_checklist(text: &str, created_at: DateTime<Utc>) -> Vec<ListItem> {
+    text.lines()
+        .filter_map(parse_checklist_line)
+        .map(|(completed, description)| ListItem {
+            completed,
+            description,
+            created_at,
+            updated_at: created_at,
+            completed_at: completed.then_some(created_at),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Split a line like `- [x] text` into its completed state and text
+fn parse_checklist_line(line: &str) -> Option<(bool, String)> {
+    let line = line.trim_start();
+    let rest = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))?
+        .trim_start();
+
+    let completed = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+
+    let description = rest[3..].trim();
+    // Do not allow empty TO-DO
+    if description.is_empty() {
+        return None;
+    }
+
+    Some((completed, description.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checklist_entries_are_read() {
+        assert_eq!(
+            parse_checklist_line("- [ ] buy milk"),
+            Some((false, "buy milk".to_string()))
+        );
+        assert_eq!(
+            parse_checklist_line("  * [x] call Bob "),
+            Some((true, "call Bob".to_string()))
+        );
+        assert_eq!(
+            parse_checklist_line("+ [X] read"),
+            Some((true, "read".to_string()))
+        );
+    }
+
+    #[test]
+    fn other_lines_are_skipped() {
+        for line in [
+            "",
+            "# Heading",
+            "- plain item",
+            "- [ ]",
+            "- [ ]   ",
+            "[x] no marker",
+            "- [-] x",
+        ] {
+            assert_eq!(parse_checklist_line(line), None, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn line_breaks_are_not_exported() {
+        let item = ListItem {
+            description: "buy\nmilk".to_string(),
+            completed: true,
+            ..Default::default()
+        };
+        assert_eq!(to_checklist([&item]), "- [x] buy milk\n");
+    }
+
+    #[test]
+    fn exported_checklists_are_imported_back() {
+        let checklist = "- [ ] buy milk\n- [x] call Bob\n";
+        let items = from_checklist(checklist, Utc::now());
+        assert_eq!(to_checklist(&items), checklist);
+        let completed = items.iter().map(|item| item.completed_at.is_some());
+        assert_eq!(completed.collect::<Vec<bool>>(), [false, true]);
+    }
+}
@@ -0,0 +1,376 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use todolist_core::{ItemId, ListItem};
+
+use crate::{
+    constants::{DATE_FORMAT, DATE_TIME_FORMAT},
+    table::display_text,
+};
+
+/// How nested headlines are turned into to-do items
+#[derive(Clone, Copy)]
+pub enum Nesting {
+    /// Every headline becomes an item keeping its outline depth
+    Nested,
+    /// Headlines become top-level items prefixed with the path of their ancestors
+    Flattened,
+}
+
+/// A headline with its planning line and property drawer
+#[derive(Default)]
+struct Headline {
+    level: usize,
+    keyword: Option<&'static str>,
+    priority: Option<char>,
+    title: String,
+    tags: Vec<String>,
+    scheduled: Option<String>,
+    deadline: Option<String>,
+    properties: Vec<(String, String)>,
+}
+
+/// Render the items as Org headlines. Line breaks in texts become spaces,
+/// as a headline or a property takes a single line.
+/// A filtered or sorted list may leave an item without its parent, so each headline
+/// is at most one level below the one before, the first one being at the top
+pub fn to_org<'a>(items: impl IntoIterator<Item = &'a ListItem>) -> String {
+    let mut org = String::new();
+    let mut previous_depth = None;
+
+    for item in items {
+        let depth = item.depth.min(previous_depth.map_or(0, |depth| depth + 1));
+        previous_depth = Some(depth);
+        org.push_str(&"*".repeat(depth + 1));
+        org.push_str(match item.completed {
+            true => " DONE ",
+            false => " TODO ",
+        });
+        if let Some(priority) = item.priority {
+            org.push_str(&format!("[#{priority}] "));
+        }
+        org.push_str(&display_text(&item.description));
+        if !item.tags.is_empty() {
+            org.push_str(&format!(" :{}:", item.tags.join(":")));
+        }
+        org.push('\n');
+
+        let planning = [("SCHEDULED", &item.scheduled), ("DEADLINE", &item.deadline)]
+            .into_iter()
+            .filter_map(|(keyword, date)| {
+                date.as_deref()
+                    .map(|date| format!("{keyword}: {}", format_timestamp(date)))
+            })
+            .collect::<Vec<String>>();
+        if !planning.is_empty() {
+            org.push_str(&planning.join(" "));
+            org.push('\n');
+        }
+
+        if !item.properties.is_empty() {
+            org.push_str(":PROPERTIES:\n");
+            for (name, value) in &item.properties {
+                org.push_str(&format!(":{name}: {}\n", display_text(value)));
+            }
+            org.push_str(":END:\n");
+        }
+    }
+
+    org
+}
+
+/// Parse Org headlines into to-do items created, and completed if done, at `created_at`
+pub fn from_org(text: &str, nesting: Nesting, created_at: DateTime<Utc>) -> Vec<ListItem> {
+    let headlines = parse_headlines(text);
+    let mut items = vec![];
+    // Titles and levels of the ancestors of the current headline
+    let mut ancestors: Vec<(usize, &str)> = vec![];
+
+    for (i, headline) in headlines.iter().enumerate() {
+        while ancestors
+            .last()
+            .is_some_and(|&(level, _)| level >= headline.level)
+        {
+            ancestors.pop();
+        }
+        let has_children = headlines
+            .get(i + 1)
+            .is_some_and(|next| next.level > headline.level);
+
+        let (description, depth) = match nesting {
+            Nesting::Nested => (headline.title.clone(), ancestors.len()),
+            // Plain headings only group their children
+            Nesting::Flattened if headline.keyword.is_none() && has_children => {
+                ancestors.push((headline.level, &headline.title));
+                continue;
+            }
+            Nesting::Flattened => {
+                let mut path = ancestors
+                    .iter()
+                    .map(|&(_, title)| title)
+                    .collect::<Vec<&str>>();
+                path.push(&headline.title);
+                (path.join(" / "), 0)
+            }
+        };
+        ancestors.push((headline.level, &headline.title));
+
+        let completed = headline.keyword == Some("DONE");
+        items.push(ListItem {
+            completed,
+            description,
+            created_at,
+            priority: headline.priority,
+            tags: headline.tags.clone(),
+            scheduled: headline.scheduled.clone(),
+            deadline: headline.deadline.clone(),
+            properties: headline.properties.clone(),
+            depth,
+            id: ItemId::new(),
+            updated_at: created_at,
+            completed_at: completed.then_some(created_at),
+        });
+    }
+
+    items
+}
+
+/// Collect the headlines along with the planning and drawer lines that follow them
+fn parse_headlines(text: &str) -> Vec<Headline> {
+    let mut headlines: Vec<Headline> = vec![];
+    let mut in_drawer = false;
+
+    for line in text.lines() {
+        if let Some(headline) = parse_headline(line) {
+            headlines.push(headline);
+            in_drawer = false;
+            continue;
+        }
+        // Text before the first headline is ignored
+        let Some(headline) = headlines.last_mut() else {
+            continue;
+        };
+
+        let line = line.trim();
+        if line == ":PROPERTIES:" {
+            in_drawer = true;
+        } else if line == ":END:" {
+            in_drawer = false;
+        } else if in_drawer {
+            if let Some((name, value)) =
+                line.strip_prefix(':').and_then(|line| line.split_once(':'))
+            {
+                headline
+                    .properties
+                    .push((name.to_string(), value.trim().to_string()));
+            }
+        } else {
+            if let Some(date) = planning_timestamp(line, "SCHEDULED:") {
+                headline.scheduled = Some(date);
+            }
+            if let Some(date) = planning_timestamp(line, "DEADLINE:") {
+                headline.deadline = Some(date);
+            }
+        }
+    }
+
+    headlines
+}
+
+/// Split a line like `** TODO [#A] Title :tag:` into its parts
+fn parse_headline(line: &str) -> Option<Headline> {
+    let level = line.chars().take_while(|&c| c == '*').count();
+    if level == 0 {
+        return None;
+    }
+    let mut rest = line[level..].strip_prefix(' ')?.trim();
+
+    // Only the first word can be the keyword, the rest being the title
+    let mut keyword = None;
+    for candidate in ["TODO", "DONE"] {
+        if let Some(after) = rest.strip_prefix(candidate) {
+            if after.is_empty() || after.starts_with(char::is_whitespace) {
+                keyword = Some(candidate);
+                rest = after.trim_start();
+                break;
+            }
+        }
+    }
+
+    let mut priority = None;
+    if let Some(after) = rest.strip_prefix("[#") {
+        let mut chars = after.chars();
+        if let (Some(letter), Some(']')) = (chars.next(), chars.next()) {
+            priority = Some(letter);
+            rest = chars.as_str().trim_start();
+        }
+    }
+
+    let mut tags = vec![];
+    if let Some((title, last)) = rest.rsplit_once(char::is_whitespace) {
+        if last.len() > 2 && last.starts_with(':') && last.ends_with(':') {
+            tags = last[1..last.len() - 1]
+                .split(':')
+                .map(String::from)
+                .collect();
+            rest = title.trim_end();
+        }
+    }
+
+    Some(Headline {
+        level,
+        keyword,
+        priority,
+        title: rest.to_string(),
+        tags,
+        ..Default::default()
+    })
+}
+
+/// Extract the date that follows a planning keyword, e.g. `DEADLINE: <2024-05-01 Wed>`
+fn planning_timestamp(line: &str, keyword: &str) -> Option<String> {
+    let (_, after) = line.split_once(keyword)?;
+    let after = after.trim_start().strip_prefix('<')?;
+    let (timestamp, _) = after.split_once('>')?;
+    parse_timestamp(timestamp)
+}
+
+/// Convert the inside of an Org timestamp (`2024-05-01 Wed 10:00 +1w`)
+/// to the storage format, dropping the day name and repeaters
+fn parse_timestamp(timestamp: &str) -> Option<String> {
+    let mut parts = timestamp.split_whitespace();
+    let date = NaiveDate::parse_from_str(parts.next()?, DATE_FORMAT).ok()?;
+    // Time ranges like `10:00-11:00` keep their start
+    let time = parts
+        .find(|part| part.contains(':'))
+        .and_then(|part| part.split('-').next());
+
+    match time {
+        Some(time) => Some(format!("{} {time}", date.format(DATE_FORMAT))),
+        None => Some(date.format(DATE_FORMAT).to_string()),
+    }
+}
+
+/// Render a stored date as an active Org timestamp
+fn format_timestamp(date: &str) -> String {
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(date, DATE_TIME_FORMAT) {
+        datetime.format("<%Y-%m-%d %a %H:%M>").to_string()
+    } else if let Ok(date) = NaiveDate::parse_from_str(date, DATE_FORMAT) {
+        date.format("<%Y-%m-%d %a>").to_string()
+    } else {
+        format!("<{date}>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTLINE: &str = "\
+Notes before the first headline
+* Home
+** TODO [#A] Buy milk :shop:errand:
+SCHEDULED: <2024-05-01 Wed 10:00-11:00 +1w> DEADLINE: <2024-05-03 Fri>
+:PROPERTIES:
+:EFFORT: 0:30
+:END:
+** DONE Call Bob
+* TODO Read
+";
+
+    fn descriptions(items: &[ListItem]) -> Vec<&str> {
+        items.iter().map(|item| item.description.as_str()).collect()
+    }
+
+    #[test]
+    fn nested_headlines_keep_their_depth() {
+        let items = from_org(OUTLINE, Nesting::Nested, Utc::now());
+        assert_eq!(
+            descriptions(&items),
+            ["Home", "Buy milk", "Call Bob", "Read"]
+        );
+        let depths = items.iter().map(|item| item.depth);
+        assert_eq!(depths.collect::<Vec<usize>>(), [0, 1, 1, 0]);
+
+        let milk = &items[1];
+        assert_eq!(milk.priority, Some('A'));
+        assert_eq!(milk.tags, ["shop", "errand"]);
+        assert_eq!(milk.scheduled.as_deref(), Some("2024-05-01 10:00"));
+        assert_eq!(milk.deadline.as_deref(), Some("2024-05-03"));
+        assert_eq!(
+            milk.properties,
+            [("EFFORT".to_string(), "0:30".to_string())]
+        );
+    }
+
+    #[test]
+    fn flattened_headlines_carry_the_path_of_their_headings() {
+        let items = from_org(OUTLINE, Nesting::Flattened, Utc::now());
+        assert_eq!(
+            descriptions(&items),
+            ["Home / Buy milk", "Home / Call Bob", "Read"]
+        );
+        assert!(items.iter().all(|item| item.depth == 0));
+    }
+
+    #[test]
+    fn only_the_first_word_is_a_keyword() {
+        let items = from_org(
+            "* TODO DONE x\n* DONE TODO y\n* TODOLIST\n",
+            Nesting::Nested,
+            Utc::now(),
+        );
+        assert_eq!(descriptions(&items), ["DONE x", "TODO y", "TODOLIST"]);
+        let completed = items.iter().map(|item| item.completed);
+        assert_eq!(completed.collect::<Vec<bool>>(), [false, true, false]);
+    }
+
+    #[test]
+    fn tags_follow_any_whitespace() {
+        let items = from_org("* TODO x\t:tag:\n* y  :a:b:\n", Nesting::Nested, Utc::now());
+        assert_eq!(descriptions(&items), ["x", "y"]);
+        assert_eq!(items[0].tags, ["tag"]);
+        assert_eq!(items[1].tags, ["a", "b"]);
+    }
+
+    #[test]
+    fn headlines_are_at_most_one_level_below_the_one_before() {
+        let item = |description: &str, depth| ListItem {
+            description: description.to_string(),
+            depth,
+            ..Default::default()
+        };
+        // Children without their parent, as in a filtered list
+        let items = [item("a", 2), item("b", 3), item("c", 1), item("d", 0)];
+        assert_eq!(to_org(&items), "* TODO a\n** TODO b\n** TODO c\n* TODO d\n");
+    }
+
+    #[test]
+    fn line_breaks_are_not_exported() {
+        let item = ListItem {
+            description: "buy\nmilk".to_string(),
+            properties: vec![("NOTE".to_string(), "semi\nskimmed".to_string())],
+            ..Default::default()
+        };
+        assert_eq!(
+            to_org([&item]),
+            "* TODO buy milk\n:PROPERTIES:\n:NOTE: semi skimmed\n:END:\n"
+        );
+    }
+
+    #[test]
+    fn exported_headlines_are_imported_back() {
+        let created_at = Utc::now();
+        let items = from_org(OUTLINE, Nesting::Nested, created_at);
+        let imported = from_org(&to_org(&items), Nesting::Nested, created_at);
+        assert_eq!(imported.len(), items.len());
+        for (item, imported) in items.iter().zip(&imported) {
+            assert_eq!(
+                ListItem {
+                    id: item.id,
+                    ..imported.clone()
+                },
+                *item
+            );
+        }
+    }
+}
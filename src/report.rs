@@ -0,0 +1,374 @@
+use chrono::Utc;
+use fltk::{
+    draw,
+    enums::{Color, Font},
+    prelude::FltkError,
+    printer::Printer,
+};
+use todolist_core::ListItem;
+
+use crate::{
+    datetime::DateFormat,
+    i18n::{tr, tr_args},
+    table::draw_line,
+};
+
+/// Message ids of the groupings
+pub const GROUPINGS: [&str; 4] = [
+    "grouping-none",
+    "grouping-status",
+    "grouping-priority",
+    "grouping-tag",
+];
+
+/// How the report groups its items
+#[derive(Clone, Copy)]
+pub enum Grouping {
+    None,
+    Status,
+    Priority,
+    Tag,
+}
+
+impl Grouping {
+    /// Grouping matching an entry of `GROUPINGS`
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            1 => Grouping::Status,
+            2 => Grouping::Priority,
+            3 => Grouping::Tag,
+            _ => Grouping::None,
+        }
+    }
+}
+
+/// Snapshot of the filtered list shared by the HTML and the printed report
+pub struct Report<'a> {
+    date_format: DateFormat,
+    generated: String,
+    total: usize,
+    completed: usize,
+    groups: Vec<(String, Vec<&'a ListItem>)>,
+}
+
+/// Message ids of the column headers
+const COLUMNS: [&str; 4] = [
+    "report-description",
+    "report-created",
+    "report-deadline",
+    "report-status",
+];
+/// Share of the printable width taken by each column
+const COLUMN_WEIGHTS: [i32; 4] = [50, 20, 15, 15];
+
+impl<'a> Report<'a> {
+    pub fn new(
+        items: impl IntoIterator<Item = &'a ListItem>,
+        grouping: Grouping,
+        date_format: DateFormat,
+    ) -> Self {
+        let items = items.into_iter().collect::<Vec<&ListItem>>();
+        let mut groups: Vec<(String, Vec<&ListItem>)> = vec![];
+        let mut add = |group: String, item: &'a ListItem| match groups
+            .iter_mut()
+            .find(|(name, _)| *name == group)
+        {
+            Some((_, members)) => members.push(item),
+            None => groups.push((group, vec![item])),
+        };
+
+        for &item in &items {
+            match grouping {
+                Grouping::None => add(String::new(), item),
+                Grouping::Status => add(status(item), item),
+                Grouping::Priority => add(
+                    match item.priority {
+                        Some(priority) => tr_args("report-priority", &[("priority", &priority)]),
+                        None => tr("report-no-priority"),
+                    },
+                    item,
+                ),
+                Grouping::Tag if item.tags.is_empty() => add(tr("report-untagged"), item),
+                Grouping::Tag => {
+                    for tag in &item.tags {
+                        add(tag.clone(), item);
+                    }
+                }
+            }
+        }
+        if !matches!(grouping, Grouping::None) {
+            groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+
+        Self {
+            generated: date_format.format(Utc::now()),
+            date_format,
+            total: items.len(),
+            completed: items.iter().filter(|item| item.completed).count(),
+            groups,
+        }
+    }
+
+    fn summary(&self) -> String {
+        tr_args(
+            "report-summary",
+            &[
+                ("total", &self.total),
+                ("completed", &self.completed),
+                ("pending", &(self.total - self.completed)),
+                ("generated", &self.generated),
+            ],
+        )
+    }
+
+    /// Render the report as a standalone HTML page
+    pub fn to_html(&self) -> String {
+        let title = escape_html(&tr("report-title"));
+        let mut html = format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; color: #222; }}
+.summary {{ color: #666; }}
+table {{ border-collapse: collapse; width: 100%; margin-bottom: 1.5em; }}
+th, td {{ text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #ddd; }}
+th {{ background: #eee; }}
+tr.completed td:first-child {{ text-decoration: line-through; color: #888; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+"#,
+        );
+        html.push_str(&format!(
+            "<p class=\"summary\">{}</p>\n",
+            escape_html(&self.summary())
+        ));
+
+        for (group, items) in &self.groups {
+            if !group.is_empty() {
+                html.push_str(&format!("<h2>{}</h2>\n", escape_html(group)));
+            }
+            html.push_str("<table>\n<tr>");
+            for column in COLUMNS {
+                html.push_str(&format!("<th>{}</th>", escape_html(&tr(column))));
+            }
+            html.push_str("</tr>\n");
+            for item in items {
+                html.push_str(match item.completed {
+                    true => "<tr class=\"completed\">",
+                    false => "<tr>",
+                });
+                for cell in row(item, &self.date_format) {
+                    html.push_str(&format!("<td>{}</td>", escape_html(&cell)));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Print the report with the same layout as the HTML page.
+    /// The page and the job are ended even when drawing fails, so the printer is not left waiting
+    pub fn print(&self) -> Result<(), FltkError> {
+        let mut printer = Printer::default();
+        printer.begin_job(0)?;
+        let printed = printer
+            .begin_page()
+            .and_then(|()| self.draw_pages(&mut printer));
+        let ended = printer.end_page();
+        printer.end_job();
+        printed.and(ended)
+    }
+
+    /// Draw the report on the page begun, beginning new ones as it fills them
+    fn draw_pages(&self, printer: &mut Printer) -> Result<(), FltkError> {
+        let (width, height) = printer.printable_rect();
+        let line_height = 18;
+        let mut y = 0;
+
+        draw::set_draw_color(Color::Black);
+        draw::set_font(Font::HelveticaBold, 18);
+        draw_line(&tr("report-title"), 0, y, width, 24);
+        y += 24;
+        draw::set_font(Font::Helvetica, 10);
+        draw_line(&self.summary(), 0, y, width, line_height);
+        y += line_height * 2;
+
+        for (group, items) in &self.groups {
+            if !group.is_empty() {
+                if y + line_height * 3 > height {
+                    printer.end_page()?;
+                    printer.begin_page()?;
+                    y = 0;
+                }
+                draw::set_font(Font::HelveticaBold, 14);
+                draw_line(group, 0, y, width, line_height + 4);
+                y += line_height + 4;
+            }
+
+            draw::set_font(Font::HelveticaBold, 10);
+            draw_row(&COLUMNS.map(tr), y, width, line_height);
+            y += line_height;
+
+            draw::set_font(Font::Helvetica, 10);
+            for item in items {
+                if y + line_height > height {
+                    printer.end_page()?;
+                    printer.begin_page()?;
+                    y = 0;
+                }
+                draw_row(&row(item, &self.date_format), y, width, line_height);
+                y += line_height;
+            }
+            y += line_height;
+        }
+        Ok(())
+    }
+}
+
+fn status(item: &ListItem) -> String {
+    tr(match item.completed {
+        true => "report-completed",
+        false => "report-pending",
+    })
+}
+
+/// Cells of an item, in the order of `COLUMNS`
+fn row(item: &ListItem, date_format: &DateFormat) -> [String; 4] {
+    [
+        item.description.clone(),
+        date_format.format(item.created_at),
+        item.deadline.clone().unwrap_or_default(),
+        status(item),
+    ]
+}
+
+/// Draw the cells of a row, with an ellipsis on the text that does not fit its column
+fn draw_row(cells: &[String; 4], y: i32, width: i32, height: i32) {
+    let mut x = 0;
+    for (cell, weight) in cells.iter().zip(COLUMN_WEIGHTS) {
+        let column_width = width * weight / 100;
+        draw_line(cell, x, y, column_width - 4, height);
+        x += column_width;
+    }
+    draw::draw_line(0, y + height - 1, width, y + height - 1);
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(description: &str, completed: bool, priority: Option<char>, tags: &[&str]) -> ListItem {
+        ListItem {
+            description: description.to_string(),
+            completed,
+            priority,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn report(items: &[ListItem], grouping: Grouping) -> Report<'_> {
+        Report::new(items, grouping, DateFormat::Pattern("%Y-%m-%d".to_string()))
+    }
+
+    /// Names of the groups, with the descriptions of their items
+    fn grouped(report: &Report) -> Vec<(String, Vec<String>)> {
+        let descriptions =
+            |items: &Vec<&ListItem>| items.iter().map(|item| item.description.clone()).collect();
+        report
+            .groups
+            .iter()
+            .map(|(name, items)| (name.clone(), descriptions(items)))
+            .collect()
+    }
+
+    fn group(name: &str, descriptions: &[&str]) -> (String, Vec<String>) {
+        let descriptions = descriptions
+            .iter()
+            .map(|description| description.to_string());
+        (name.to_string(), descriptions.collect())
+    }
+
+    #[test]
+    fn items_are_in_the_group_of_each_of_their_tags() {
+        let items = [
+            item("a", false, None, &["work", "home"]),
+            item("b", false, None, &[]),
+            item("c", false, None, &["home"]),
+        ];
+        assert_eq!(
+            grouped(&report(&items, Grouping::Tag)),
+            [
+                group("Untagged", &["b"]),
+                group("home", &["a", "c"]),
+                group("work", &["a"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn items_without_a_priority_are_grouped_apart() {
+        let items = [
+            item("a", false, Some('A'), &[]),
+            item("b", false, None, &[]),
+            item("c", false, Some('A'), &[]),
+            item("d", false, Some('B'), &[]),
+        ];
+        assert_eq!(
+            grouped(&report(&items, Grouping::Priority)),
+            [
+                group("No priority", &["b"]),
+                group("Priority A", &["a", "c"]),
+                group("Priority B", &["d"]),
+            ]
+        );
+        let by_status = grouped(&report(&items[..2], Grouping::Status));
+        assert_eq!(by_status, [group("Pending", &["a", "b"])]);
+    }
+
+    #[test]
+    fn items_in_several_groups_are_counted_once() {
+        let items = [
+            item("a", true, None, &["work", "home"]),
+            item("b", false, None, &["work"]),
+            item("c", true, None, &[]),
+        ];
+        let summary = report(&items, Grouping::Tag).summary();
+        assert!(
+            summary.starts_with("3 items • 2 completed • 1 pending • generated "),
+            "{summary}"
+        );
+    }
+
+    #[test]
+    fn html_escapes_the_text_of_the_items() {
+        let items = [item(
+            r#"<b>Fish & "chips"</b> at Bob's"#,
+            false,
+            None,
+            &["<tag>"],
+        )];
+        let html = report(&items, Grouping::Tag).to_html();
+        assert!(
+            html.contains("<td>&lt;b&gt;Fish &amp; &quot;chips&quot;&lt;/b&gt; at Bob&#39;s</td>")
+        );
+        assert!(html.contains("<h2>&lt;tag&gt;</h2>"));
+        assert!(!html.contains("<b>") && !html.contains("<tag>"));
+    }
+}
@@ -0,0 +1,111 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use fltk::app;
+use resolve_path::PathResolveExt;
+use serde::{Deserialize, Serialize};
+
+use todolist_core::{ItemId, SortOrder};
+
+/// UI state of a data file, restored when the next launch opens it
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Session {
+    /// Position and size of the main window: `[x, y, w, h]`
+    pub window: Option<[i32; 4]>,
+    pub column_widths: Vec<i32>,
+    pub sort_order: SortOrder,
+    pub filter: String,
+    /// The selected item
+    pub selected_id: Option<ItemId>,
+}
+
+/// Location of the sessions file: `$XDG_DATA_HOME/rust-fltk-todolist/sessions.json`,
+/// holding the session of each data file by its absolute path
+pub fn sessions_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("rust-fltk-todolist").join("sessions.json"))
+}
+
+/// Sessions of the sessions file, by data file
+fn read_sessions(path: &Path) -> BTreeMap<String, Session> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|data| {
+            serde_json::from_str(&data)
+                .map_err(|err| eprintln!("{err:?}"))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// Key of a data file in the sessions file, the same from any working directory
+fn session_key(data_path: &str) -> String {
+    data_path.resolve().to_string_lossy().into_owned()
+}
+
+/// Save the UI state of the data file to the sessions file, if there is one
+pub fn dump_session(session: &Session, data_path: &str, path: Option<&Path>) {
+    let Some(path) = path else {
+        return;
+    };
+    let mut sessions = read_sessions(path);
+    sessions.insert(session_key(data_path), session.clone());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|err| eprintln!("{err:?}"))
+            .unwrap_or_default();
+    }
+    fs::write(path, serde_json::to_string_pretty(&sessions).unwrap())
+        .map_err(|err| eprintln!("{err:?}"))
+        .unwrap_or_default();
+}
+
+/// Load the UI state of the data file from the sessions file, if there is one
+pub fn load_session(data_path: &str, path: Option<&Path>) -> Session {
+    path.map(read_sessions)
+        .and_then(|mut sessions| sessions.remove(&session_key(data_path)))
+        .unwrap_or_default()
+}
+
+/// Whether the title bar of a window at this position would be reachable
+/// on one of the connected screens
+pub fn is_on_screen([x, y, w, _]: [i32; 4]) -> bool {
+    (0..app::screen_count()).any(|n| {
+        let (sx, sy, sw, sh) = app::screen_work_area(n);
+        x < sx + sw && x + w > sx && y >= sy && y < sy + sh
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn sessions_are_kept_by_data_file() {
+        let dir = env::temp_dir().join(format!("todolist-session-{}", process::id()));
+        let path = dir.join("sessions.json");
+        let session = |filter: &str| Session {
+            filter: filter.to_string(),
+            ..Default::default()
+        };
+        let (home, work) = (dir.join("home.dat"), dir.join("work.dat"));
+        let (home, work) = (home.to_str().unwrap(), work.to_str().unwrap());
+        dump_session(&session("milk"), home, Some(&path));
+        dump_session(&session("report"), work, Some(&path));
+
+        assert_eq!(load_session(home, Some(&path)).filter, "milk");
+        assert_eq!(load_session(work, Some(&path)).filter, "report");
+        let other = dir.join("other.dat");
+        assert_eq!(
+            load_session(other.to_str().unwrap(), Some(&path)).filter,
+            ""
+        );
+        assert_eq!(load_session(home, None).filter, "");
+        fs::remove_dir_all(&dir).unwrap_or_default();
+    }
+}
@@ -0,0 +1,448 @@
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use fltk::{
+    app::{self, MouseButton, Sender},
+    draw,
+    enums::{Color, Event, Font, FrameType},
+    prelude::{GroupExt, TableExt, WidgetExt},
+    table::{TableContext, TableRow, TableRowSelectFlag, TableRowSelectMode},
+};
+
+use crate::{i18n::tr, theme::Palette, utils::Message};
+
+/// Cells of a list row, copied from the model when the list is rebuilt
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    pub description: String,
+    pub datetime: String,
+    pub completed: bool,
+    /// Outline depth, drawn as an indent before the description
+    pub depth: usize,
+}
+
+/// Message ids of the column headers
+const COLUMNS: [&str; 3] = ["column-description", "column-datetime", "column-completed"];
+pub const DESCRIPTION_COLUMN: i32 = 0;
+pub const DATETIME_COLUMN: i32 = 1;
+pub const COMPLETED_COLUMN: i32 = 2;
+
+/// Space between the cell borders and their text
+const CELL_PADDING: i32 = 4;
+/// Indent of the description for each outline level
+const INDENT: i32 = 16;
+/// Side of the box drawn in the completed cells
+const CHECKBOX_SIZE: i32 = 13;
+/// Width of the frame around the cells
+const TABLE_BORDER: i32 = 2;
+
+/// List of the to-dos with resizable columns.
+/// Only the cells on screen are drawn, so long lists scroll as fast as short ones
+#[derive(Clone)]
+pub struct TodoTable {
+    table: TableRow,
+    rows: Rc<RefCell<Vec<Row>>>,
+    /// Row shown in the form, the last one clicked or selected
+    current: Rc<Cell<Option<i32>>>,
+    /// Column the rows are sorted by, and whether in ascending order
+    sorted_by: Rc<Cell<Option<(i32, bool)>>>,
+    palette: Rc<Cell<Palette>>,
+    text_size: Rc<Cell<i32>>,
+}
+
+fltk::widget_extends!(TodoTable, TableRow, table);
+
+impl TodoTable {
+    /// Empty table sending `Message::Select` when a row is clicked
+    /// and `Message::SortColumn` with the column when a column header is
+    pub fn new(sender: Sender<Message>, palette: Palette) -> Self {
+        let mut table = TableRow::default();
+        table.set_type(TableRowSelectMode::Multi);
+        table.set_color(Color::Background2);
+        table.set_cols(COLUMNS.len() as i32);
+        table.set_col_header(true);
+        table.set_col_resize(true);
+        table.set_col_resize_min(CHECKBOX_SIZE + CELL_PADDING * 2);
+        table.end();
+
+        let mut todo_table = Self {
+            table,
+            rows: Rc::default(),
+            current: Rc::default(),
+            sorted_by: Rc::default(),
+            palette: Rc::new(Cell::new(palette)),
+            text_size: Rc::new(Cell::new(app::font_size())),
+        };
+        todo_table.set_text_size(app::font_size());
+
+        todo_table.table.set_callback({
+            let current = todo_table.current.clone();
+            move |table| match table.callback_context() {
+                TableContext::Cell => {
+                    let row = table.callback_row();
+                    // Only the left button selects, a right click opens a menu on the row under it
+                    if app::event() == Event::Push
+                        && app::event_mouse_button() == MouseButton::Right
+                        && !table.row_selected(row)
+                    {
+                        table.select_all_rows(TableRowSelectFlag::Deselect);
+                        table
+                            .select_row(row, TableRowSelectFlag::Select)
+                            .unwrap_or_default();
+                    }
+                    current.set(Some(row));
+                    sender.send(Message::Select);
+                }
+                TableContext::ColHeader if app::event() == Event::Released => {
+                    sender.send(Message::SortColumn(table.callback_col()));
+                }
+                _ => {}
+            }
+        });
+
+        todo_table.table.draw_cell({
+            let rows = todo_table.rows.clone();
+            let sorted_by = todo_table.sorted_by.clone();
+            let palette = todo_table.palette.clone();
+            let text_size = todo_table.text_size.clone();
+            let titles = COLUMNS.map(tr);
+            move |table, context, row, col, x, y, w, h| match context {
+                TableContext::StartPage => draw::set_font(Font::Helvetica, text_size.get()),
+                TableContext::ColHeader => {
+                    draw::push_clip(x, y, w, h);
+                    draw::draw_box(FrameType::ThinUpBox, x, y, w, h, table.col_header_color());
+                    draw::set_draw_color(palette.get().header);
+                    let title = match sorted_by.get() {
+                        Some((sorted, true)) if sorted == col => {
+                            format!("{} ▲", titles[col as usize])
+                        }
+                        Some((sorted, false)) if sorted == col => {
+                            format!("{} ▼", titles[col as usize])
+                        }
+                        _ => titles[col as usize].clone(),
+                    };
+                    draw_line(&title, x + CELL_PADDING, y, w - CELL_PADDING * 2, h);
+                    draw::pop_clip();
+                }
+                TableContext::Cell => {
+                    let rows = rows.borrow();
+                    let Some(item) = rows.get(row as usize) else {
+                        return;
+                    };
+                    let selected = table.row_selected(row);
+                    let background = match (selected, row % 2) {
+                        (true, _) => table.selection_color(),
+                        // Every other row a shade towards the text colour
+                        (false, 1) => {
+                            Color::color_average(table.color(), table.label_color(), 0.92)
+                        }
+                        (false, _) => table.color(),
+                    };
+
+                    draw::push_clip(x, y, w, h);
+                    draw::draw_rect_fill(x, y, w, h, background);
+                    draw::set_draw_color(Color::contrast(table.label_color(), background));
+                    match col {
+                        COMPLETED_COLUMN => {
+                            let (bx, by) =
+                                (x + (w - CHECKBOX_SIZE) / 2, y + (h - CHECKBOX_SIZE) / 2);
+                            draw::draw_box(
+                                FrameType::DownBox,
+                                bx,
+                                by,
+                                CHECKBOX_SIZE,
+                                CHECKBOX_SIZE,
+                                Color::Background2,
+                            );
+                            if item.completed {
+                                draw::draw_check(
+                                    bx + 2,
+                                    by + 2,
+                                    CHECKBOX_SIZE - 4,
+                                    CHECKBOX_SIZE - 4,
+                                    Color::contrast(Color::Foreground, Color::Background2),
+                                );
+                            }
+                        }
+                        DATETIME_COLUMN => {
+                            draw_line(&item.datetime, x + CELL_PADDING, y, w - CELL_PADDING * 2, h)
+                        }
+                        _ => {
+                            let indent = item.depth as i32 * INDENT;
+                            draw_line(
+                                &item.description,
+                                x + CELL_PADDING + indent,
+                                y,
+                                w - CELL_PADDING * 2 - indent,
+                                h,
+                            );
+                        }
+                    }
+                    draw::pop_clip();
+                }
+                _ => {}
+            }
+        });
+
+        todo_table
+    }
+
+    /// Replace the rows, clearing the selection
+    pub fn show_rows(&mut self, rows: Vec<Row>) {
+        self.table.select_all_rows(TableRowSelectFlag::Deselect);
+        self.current.set(None);
+        self.table.set_rows(rows.len() as i32);
+        *self.rows.borrow_mut() = rows;
+        self.table.redraw();
+    }
+
+    /// Replace the text of the rows, keeping the selection, when they are the same items
+    pub fn update_rows(&mut self, rows: Vec<Row>) {
+        if rows.len() != self.rows.borrow().len() {
+            return self.show_rows(rows);
+        }
+        *self.rows.borrow_mut() = rows;
+        self.table.redraw();
+    }
+
+    /// Rows as last shown
+    #[cfg(test)]
+    pub fn rows(&self) -> Vec<Row> {
+        self.rows.borrow().clone()
+    }
+
+    /// Image of a cell as drawn, `None` if it is out of view
+    #[cfg(test)]
+    pub fn cell_image(&self, row: i32, col: i32) -> Option<fltk::image::RgbImage> {
+        use fltk::{prelude::SurfaceDevice, surface::ImageSurface};
+
+        let (x, y, w, h) = self.table.find_cell(TableContext::Cell, row, col)?;
+        let surface = ImageSurface::new(w, h, false);
+        ImageSurface::push_current(&surface);
+        // The table drawn with the cell at the origin, clipped to the cell
+        surface.draw(&self.table, self.table.x() - x, self.table.y() - y);
+        ImageSurface::pop_current();
+        surface.image()
+    }
+
+    /// Row shown in the form, `None` if it was unselected
+    pub fn current(&mut self) -> Option<i32> {
+        self.current
+            .get()
+            .filter(|&row| row < self.table.rows() && self.table.row_selected(row))
+    }
+
+    /// Select exactly these rows, `current` being the one shown in the form
+    pub fn set_selection(&mut self, rows: &[i32], current: Option<i32>) {
+        self.table.select_all_rows(TableRowSelectFlag::Deselect);
+        for &row in rows {
+            self.table
+                .select_row(row, TableRowSelectFlag::Select)
+                .unwrap_or_default();
+        }
+        self.current.set(current);
+    }
+
+    /// Selected rows, from the top
+    pub fn selected_rows(&mut self) -> Vec<i32> {
+        (0..self.table.rows())
+            .filter(|&row| self.table.row_selected(row))
+            .collect()
+    }
+
+    /// Scroll the row into view if it is not
+    pub fn make_visible(&mut self, row: i32) {
+        let (top, _, bottom, _) = self.table.visible_cells();
+        if row <= top {
+            self.table.set_row_position(row);
+        } else if row >= bottom {
+            // The bottom row may be partly hidden
+            self.table.set_row_position(row - (bottom - top - 1).max(0));
+        }
+    }
+
+    /// Window area of a cell
+    pub fn cell_bounds(&self, row: i32, col: i32) -> Option<(i32, i32, i32, i32)> {
+        self.table.find_cell(TableContext::Cell, row, col)
+    }
+
+    /// Row and column of the cell under the mouse pointer
+    pub fn cell_at_pointer(&self) -> Option<(i32, i32)> {
+        match self.table.cursor2rowcol() {
+            Some((TableContext::Cell, row, col, _)) => Some((row, col)),
+            _ => None,
+        }
+    }
+
+    /// Show an arrow in the header of the column the rows are sorted by
+    pub fn set_sorted_by(&mut self, sorted_by: Option<(i32, bool)>) {
+        if self.sorted_by.replace(sorted_by) != sorted_by {
+            self.table.redraw();
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette.set(palette);
+        self.table.redraw();
+    }
+
+    /// Font size of the cells, the rows getting the matching height
+    pub fn set_text_size(&mut self, size: i32) {
+        self.text_size.set(size);
+        draw::set_font(Font::Helvetica, size);
+        let height = draw::height() + CELL_PADDING;
+        self.table.set_row_height_all(height);
+        self.table.set_col_header_height(height + CELL_PADDING);
+        self.table.redraw();
+    }
+
+    pub fn column_widths(&self) -> Vec<i32> {
+        (0..self.table.cols())
+            .map(|col| self.table.col_width(col))
+            .collect()
+    }
+
+    pub fn set_column_widths(&mut self, widths: &[i32]) {
+        for (col, &width) in widths.iter().enumerate() {
+            self.table.set_col_width(col as i32, width);
+        }
+    }
+
+    /// Scale the columns to the width of the table, keeping their proportions
+    pub fn fit_columns(&mut self) {
+        let scrollbar = self.table.scrollbar_size().max(app::scrollbar_size());
+        let columns_width = self.table.w() - scrollbar - TABLE_BORDER * 2;
+        let widths = self.column_widths();
+        let total = widths.iter().sum::<i32>().max(1);
+        self.set_column_widths(
+            &widths
+                .iter()
+                .map(|width| (width * columns_width / total).max(1))
+                .collect::<Vec<i32>>(),
+        );
+        self.table.redraw();
+    }
+}
+
+/// Draw a line of text as is, vertically centred, with an ellipsis if it is too long.
+/// Unlike labels, `draw_text` reads no `@` symbols, so user text needs no escaping
+pub fn draw_line(text: &str, x: i32, y: i32, w: i32, h: i32) {
+    let baseline = y + (h + draw::height()) / 2 - draw::descent();
+    let text = display_text(text);
+    draw::draw_text(&ellipsize(&text, w, draw::width), x, baseline);
+}
+
+/// Text of a cell on a single line, with tabs, newlines
+/// and other control characters shown as spaces
+pub fn display_text(text: &str) -> Cow<'_, str> {
+    match text.contains(char::is_control) {
+        true => Cow::Owned(
+            text.chars()
+                .map(|c| if c.is_control() { ' ' } else { c })
+                .collect(),
+        ),
+        false => Cow::Borrowed(text),
+    }
+}
+
+/// Text cut with an ellipsis to fit the width, as measured by `measure`
+fn ellipsize(text: &str, width: i32, measure: impl Fn(&str) -> f64) -> Cow<'_, str> {
+    if measure(text) <= width as f64 {
+        return Cow::Borrowed(text);
+    }
+    let room = width as f64 - measure("…");
+    let ends = text
+        .char_indices()
+        .map(|(index, _)| index)
+        .skip(1)
+        .chain([text.len()])
+        .collect::<Vec<usize>>();
+
+    // Number of characters that fit before the ellipsis
+    let (mut low, mut high) = (0, ends.len());
+    while low < high {
+        let middle = (low + high).div_ceil(2);
+        match measure(&text[..ends[middle - 1]]) <= room {
+            true => low = middle,
+            false => high = middle - 1,
+        }
+    }
+
+    let kept = match low {
+        0 => "",
+        n => &text[..ends[n - 1]],
+    };
+    Cow::Owned(format!("{kept}…"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One unit per character, like a monospace font
+    fn chars(text: &str) -> f64 {
+        text.chars().count() as f64
+    }
+
+    #[test]
+    fn format_codes_are_kept_as_typed() {
+        for text in [
+            "@C1 call Bob",
+            "@b meeting",
+            "@@",
+            "@.",
+            "ends with @-> arrow",
+            "a@b",
+        ] {
+            assert_eq!(display_text(text), text);
+        }
+    }
+
+    #[test]
+    fn control_characters_become_spaces() {
+        assert_eq!(display_text("buy\tmilk"), "buy milk");
+        assert_eq!(display_text("first\nsecond\r\n"), "first second  ");
+        assert_eq!(display_text("bell\u{7}"), "bell ");
+    }
+
+    #[test]
+    fn rtl_and_emoji_are_untouched() {
+        for text in [
+            "שלום עולם",
+            "مرحبا بالعالم",
+            "🎉 party 👩‍👩‍👧",
+            "e\u{301}te\u{301}",
+        ] {
+            assert_eq!(display_text(text), text);
+            assert_eq!(ellipsize(text, 100, chars), text);
+        }
+    }
+
+    #[test]
+    fn short_text_is_not_cut() {
+        assert_eq!(ellipsize("milk", 4, chars), "milk");
+        assert_eq!(ellipsize("", 0, chars), "");
+    }
+
+    #[test]
+    fn long_text_ends_with_an_ellipsis() {
+        let text = "x".repeat(100_000);
+        let cut = ellipsize(&text, 10, chars);
+        assert_eq!(cut, format!("{}…", "x".repeat(9)));
+    }
+
+    #[test]
+    fn cuts_fall_on_character_boundaries() {
+        assert_eq!(ellipsize("🎉🎉🎉🎉", 3, chars), "🎉🎉…");
+        assert_eq!(ellipsize("שלום עולם", 5, chars), "שלום…");
+    }
+
+    #[test]
+    fn no_room_leaves_the_ellipsis_alone() {
+        assert_eq!(ellipsize("milk", 1, chars), "…");
+        assert_eq!(ellipsize("milk", 0, chars), "…");
+    }
+}
@@ -0,0 +1,485 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use todolist_core::{ItemId, ListItem};
+
+use crate::{
+    constants::{DATE_FORMAT, DATE_TIME_FORMAT},
+    i18n::{tr, tr_count},
+};
+
+/// Format of the dates in `task export`
+const TASK_DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Properties used to keep Taskwarrior attributes that `ListItem` has no field for.
+/// Other attributes, like user defined ones, are kept under their own name as JSON
+const UUID_PROPERTY: &str = "UUID";
+const PROJECT_PROPERTY: &str = "PROJECT";
+/// Status other than pending and completed, like waiting or recurring
+const STATUS_PROPERTY: &str = "STATUS";
+/// One per annotation: its date, a space, and its text
+const ANNOTATION_PROPERTY: &str = "ANNOTATION";
+
+/// Attributes with a field of their own in `Task`
+const TASK_FIELDS: [&str; 12] = [
+    "uuid",
+    "description",
+    "status",
+    "entry",
+    "modified",
+    "end",
+    "due",
+    "scheduled",
+    "priority",
+    "project",
+    "tags",
+    "annotations",
+];
+/// Computed by Taskwarrior, not data
+const COMPUTED_FIELDS: [&str; 2] = ["id", "urgency"];
+
+/// A task as written by `task export` and read by `task import`
+#[derive(Deserialize, Serialize)]
+struct Task {
+    uuid: String,
+    description: String,
+    status: String,
+    #[serde(default)]
+    entry: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    /// When the task was completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scheduled: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+    /// Attributes with no counterpart in `ListItem`
+    #[serde(flatten)]
+    other: Map<String, Value>,
+}
+
+/// A dated note of a task
+#[derive(Deserialize, Serialize)]
+struct Annotation {
+    #[serde(default)]
+    entry: String,
+    description: String,
+}
+
+/// Fields that could not be carried over, with the number of tasks using each one
+#[derive(Default)]
+pub struct MappingReport {
+    pub unsupported: BTreeMap<String, usize>,
+    /// Deleted tasks, which are not imported
+    pub skipped: usize,
+}
+
+impl MappingReport {
+    fn count(&mut self, field: &str) {
+        *self.unsupported.entry(field.to_string()).or_default() += 1;
+    }
+
+    /// Human-readable summary, `None` if everything was mapped
+    pub fn summary(&self) -> Option<String> {
+        if self.unsupported.is_empty() && self.skipped == 0 {
+            return None;
+        }
+
+        let mut lines = vec![];
+        if self.skipped > 0 {
+            lines.push(tr_count("import-skipped", self.skipped));
+        }
+        if !self.unsupported.is_empty() {
+            lines.push(tr("import-unsupported"));
+            for (field, count) in &self.unsupported {
+                lines.push(format!("  {field}: {}", tr_count("import-tasks", *count)));
+            }
+        }
+
+        Some(lines.join("\n"))
+    }
+}
+
+/// Parse the output of `task export` into to-do items
+pub fn from_json(json: &str) -> Result<(Vec<ListItem>, MappingReport), serde_json::Error> {
+    let tasks = serde_json::from_str::<Vec<Task>>(json)?;
+    let mut report = MappingReport::default();
+    let mut items = vec![];
+
+    for task in tasks {
+        if task.status == "deleted" {
+            report.skipped += 1;
+            continue;
+        }
+        let mut properties = vec![(UUID_PROPERTY.to_string(), task.uuid)];
+        if !matches!(task.status.as_str(), "pending" | "completed") {
+            properties.push((STATUS_PROPERTY.to_string(), task.status.clone()));
+        }
+        if let Some(project) = task.project {
+            properties.push((PROJECT_PROPERTY.to_string(), project));
+        }
+        for annotation in task.annotations {
+            let value = format!("{} {}", annotation.entry, annotation.description);
+            properties.push((ANNOTATION_PROPERTY.to_string(), value));
+        }
+        for (field, value) in task.other {
+            if !COMPUTED_FIELDS.contains(&field.as_str()) {
+                properties.push((field, value.to_string()));
+            }
+        }
+        let priority = match task.priority {
+            Some(priority) => match priority.as_str() {
+                "H" => Some('A'),
+                "M" => Some('B'),
+                "L" => Some('C'),
+                // Kept for the export, but not shown as a priority
+                _ => {
+                    report.count("priority");
+                    properties.push(("priority".to_string(), Value::from(priority).to_string()));
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let completed = task.status == "completed";
+        let created_at = parse_task_date(&task.entry)
+            .unwrap_or_else(Local::now)
+            .to_utc();
+        let updated_at = task.modified.as_deref().and_then(parse_task_date);
+        let completed_at = task.end.as_deref().and_then(parse_task_date);
+        items.push(ListItem {
+            completed,
+            description: task.description,
+            created_at,
+            priority,
+            tags: task.tags,
+            scheduled: task.scheduled.as_deref().and_then(task_date_to_stored),
+            deadline: task.due.as_deref().and_then(task_date_to_stored),
+            properties,
+            depth: 0,
+            id: ItemId::new(),
+            updated_at: updated_at.map_or(created_at, |at| at.to_utc()),
+            completed_at: completed.then(|| completed_at.map_or(created_at, |at| at.to_utc())),
+        });
+    }
+
+    Ok((items, report))
+}
+
+/// Render the items as a JSON array accepted by `task import`
+pub fn to_json<'a>(items: impl IntoIterator<Item = &'a ListItem>) -> (String, MappingReport) {
+    let items = items.into_iter().collect::<Vec<&ListItem>>();
+    let mut report = MappingReport::default();
+    let mut tasks = vec![];
+
+    // Copies of a task share its uuid, which stays with the oldest of them
+    let mut uuid_owners = HashMap::<&str, &ListItem>::new();
+    for &item in &items {
+        if let Some(uuid) = property(item, UUID_PROPERTY) {
+            let owner = uuid_owners.entry(uuid).or_insert(item);
+            if item.created_at < owner.created_at {
+                *owner = item;
+            }
+        }
+    }
+
+    for item in items {
+        let mut priority = item.priority.and_then(|priority| match priority {
+            'A' => Some("H".to_string()),
+            'B' => Some("M".to_string()),
+            'C' => Some("L".to_string()),
+            _ => {
+                report.count("priority");
+                None
+            }
+        });
+        let mut annotations = vec![];
+        let mut other = Map::new();
+        for (name, value) in &item.properties {
+            match name.as_str() {
+                UUID_PROPERTY | PROJECT_PROPERTY | STATUS_PROPERTY => {}
+                ANNOTATION_PROPERTY => annotations.push(annotation(value, item.updated_at)),
+                // Kept from an import, unless a priority was set since
+                "priority" if item.priority.is_none() => {
+                    priority = serde_json::from_str(value).ok();
+                }
+                name if !TASK_FIELDS.contains(&name) && !COMPUTED_FIELDS.contains(&name) => {
+                    match serde_json::from_str(value) {
+                        Ok(value) => {
+                            other.insert(name.to_string(), value);
+                        }
+                        Err(_) => report.count(&format!("property {name}")),
+                    }
+                }
+                _ => report.count(&format!("property {name}")),
+            }
+        }
+        if item.depth > 0 {
+            report.count("nesting");
+        }
+
+        tasks.push(Task {
+            uuid: match property(item, UUID_PROPERTY) {
+                Some(uuid) if uuid_owners[uuid].id == item.id => uuid.to_string(),
+                _ => uuid::Uuid::new_v4().to_string(),
+            },
+            description: item.description.clone(),
+            status: match item.completed {
+                true => "completed",
+                false => property(item, STATUS_PROPERTY).unwrap_or("pending"),
+            }
+            .to_string(),
+            entry: item.created_at.format(TASK_DATE_FORMAT).to_string(),
+            modified: Some(item.updated_at.format(TASK_DATE_FORMAT).to_string()),
+            end: item
+                .completed_at
+                .map(|at| at.format(TASK_DATE_FORMAT).to_string()),
+            due: item.deadline.as_deref().and_then(stored_to_task_date),
+            scheduled: item.scheduled.as_deref().and_then(stored_to_task_date),
+            priority,
+            project: property(item, PROJECT_PROPERTY).map(str::to_string),
+            tags: item.tags.clone(),
+            annotations,
+            other,
+        });
+    }
+
+    (serde_json::to_string_pretty(&tasks).unwrap(), report)
+}
+
+/// Value of the first property of the item with this name
+fn property<'a>(item: &'a ListItem, name: &str) -> Option<&'a str> {
+    item.properties
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Annotation of an `ANNOTATION` property, dated `at` if its date is missing
+fn annotation(value: &str, at: DateTime<Utc>) -> Annotation {
+    match value.split_once(' ') {
+        Some((entry, description)) if parse_task_date(entry).is_some() => Annotation {
+            entry: entry.to_string(),
+            description: description.to_string(),
+        },
+        _ => Annotation {
+            entry: at.format(TASK_DATE_FORMAT).to_string(),
+            description: value.to_string(),
+        },
+    }
+}
+
+/// Parse a Taskwarrior date into local time
+fn parse_task_date(date: &str) -> Option<DateTime<Local>> {
+    NaiveDateTime::parse_from_str(date, TASK_DATE_FORMAT)
+        .ok()
+        .map(|date| Utc.from_utc_datetime(&date).with_timezone(&Local))
+}
+
+/// Convert a Taskwarrior date to the storage format of due and scheduled dates
+fn task_date_to_stored(date: &str) -> Option<String> {
+    parse_task_date(date).map(|date| date.format(DATE_TIME_FORMAT).to_string())
+}
+
+/// Convert a stored due or scheduled date to a Taskwarrior date.
+/// Dates without time are taken at midnight
+fn stored_to_task_date(date: &str) -> Option<String> {
+    let date = NaiveDateTime::parse_from_str(date, DATE_TIME_FORMAT)
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(date, DATE_FORMAT)
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+
+    Local.from_local_datetime(&date).earliest().map(|date| {
+        date.with_timezone(&Utc)
+            .format(TASK_DATE_FORMAT)
+            .to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn tasks_are_imported_as_items() {
+        let json = json!([
+            {
+                "id": 1,
+                "uuid": "8f1c7f4e-7c0a-4f0e-9b2a-1d2c3e4f5a6b",
+                "description": "buy milk",
+                "status": "completed",
+                "entry": "20240101T090000Z",
+                "end": "20240102T090000Z",
+                "priority": "H",
+                "project": "home",
+                "tags": ["shop"],
+                "annotations": [{ "entry": "20240101T100000Z", "description": "semi-skimmed" }],
+                "estimate": "2h",
+                "urgency": 4.2
+            },
+            {
+                "uuid": "2b1c7f4e-7c0a-4f0e-9b2a-1d2c3e4f5a6b",
+                "description": "gone",
+                "status": "deleted"
+            }
+        ]);
+        let (items, report) = from_json(&json.to_string()).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(report.skipped, 1);
+        assert!(report.unsupported.is_empty());
+
+        let item = &items[0];
+        assert_eq!(item.description, "buy milk");
+        assert!(item.completed);
+        assert_eq!(item.priority, Some('A'));
+        assert_eq!(item.tags, ["shop"]);
+        assert_eq!(item.created_at.to_rfc3339(), "2024-01-01T09:00:00+00:00");
+        assert_eq!(
+            item.completed_at.map(|at| at.to_rfc3339()).as_deref(),
+            Some("2024-01-02T09:00:00+00:00")
+        );
+        assert_eq!(property(item, PROJECT_PROPERTY), Some("home"));
+        assert_eq!(
+            property(item, ANNOTATION_PROPERTY),
+            Some("20240101T100000Z semi-skimmed")
+        );
+        assert_eq!(property(item, "estimate"), Some("\"2h\""));
+        assert_eq!(property(item, "urgency"), None);
+    }
+
+    #[test]
+    fn unknown_priorities_are_kept_and_reported() {
+        let json = json!([{
+            "uuid": "8f1c7f4e-7c0a-4f0e-9b2a-1d2c3e4f5a6b",
+            "description": "buy milk",
+            "status": "pending",
+            "priority": "urgent"
+        }]);
+        let (items, report) = from_json(&json.to_string()).unwrap();
+        assert_eq!(items[0].priority, None);
+        assert_eq!(report.unsupported["priority"], 1);
+
+        let (exported, _) = to_json(&items);
+        let tasks = serde_json::from_str::<Value>(&exported).unwrap();
+        assert_eq!(tasks[0]["priority"], "urgent");
+    }
+
+    #[test]
+    fn items_are_exported_as_tasks() {
+        let items = [
+            ListItem {
+                description: "buy milk".to_string(),
+                priority: Some('B'),
+                deadline: Some("2024-01-05".to_string()),
+                properties: vec![("EFFORT".to_string(), "2h".to_string())],
+                depth: 1,
+                ..Default::default()
+            },
+            ListItem {
+                description: "call Bob".to_string(),
+                priority: Some('D'),
+                ..Default::default()
+            },
+        ];
+        let (json, report) = to_json(&items);
+        let tasks = serde_json::from_str::<Value>(&json).unwrap();
+        assert_eq!(tasks[0]["description"], "buy milk");
+        assert_eq!(tasks[0]["status"], "pending");
+        assert_eq!(tasks[0]["priority"], "M");
+        assert!(tasks[0]["due"].is_string());
+        assert!(tasks[0]["uuid"].is_string());
+        assert!(tasks[1].get("priority").is_none());
+        assert_eq!(report.unsupported["property EFFORT"], 1);
+        assert_eq!(report.unsupported["nesting"], 1);
+        assert_eq!(report.unsupported["priority"], 1);
+    }
+
+    #[test]
+    fn exports_of_imports_are_unchanged() {
+        let json = json!([
+            {
+                "uuid": "8f1c7f4e-7c0a-4f0e-9b2a-1d2c3e4f5a6b",
+                "description": "buy milk",
+                "status": "completed",
+                "entry": "20240101T090000Z",
+                "modified": "20240102T090000Z",
+                "end": "20240102T090000Z",
+                "due": "20240105T120000Z",
+                "priority": "L",
+                "project": "home",
+                "tags": ["shop", "weekly"],
+                "annotations": [
+                    { "entry": "20240101T100000Z", "description": "semi-skimmed" },
+                    { "entry": "20240101T110000Z", "description": "two bottles" }
+                ],
+                "estimate": "2h",
+                "reviewed": 3,
+                "wait": "20240103T090000Z"
+            },
+            {
+                "uuid": "2b1c7f4e-7c0a-4f0e-9b2a-1d2c3e4f5a6b",
+                "description": "call Bob",
+                "status": "pending",
+                "entry": "20240101T090000Z",
+                "modified": "20240101T090000Z",
+                "priority": "urgent"
+            },
+            {
+                "uuid": "5d1c7f4e-7c0a-4f0e-9b2a-1d2c3e4f5a6b",
+                "description": "water the plants",
+                "status": "recurring",
+                "entry": "20240101T090000Z",
+                "modified": "20240101T090000Z",
+                "recur": "weekly"
+            }
+        ]);
+        let (items, _) = from_json(&json.to_string()).unwrap();
+        let (exported, report) = to_json(&items);
+        assert_eq!(serde_json::from_str::<Value>(&exported).unwrap(), json);
+        assert!(report.unsupported.is_empty());
+    }
+
+    #[test]
+    fn copies_of_a_task_get_uuids_of_their_own() {
+        let json = json!([{
+            "uuid": "8f1c7f4e-7c0a-4f0e-9b2a-1d2c3e4f5a6b",
+            "description": "buy milk",
+            "status": "waiting",
+            "entry": "20240101T090000Z",
+            "wait": "20240103T090000Z"
+        }]);
+        let (mut items, _) = from_json(&json.to_string()).unwrap();
+        let mut copy = ListItem {
+            id: ItemId::new(),
+            created_at: Utc::now(),
+            ..items[0].clone()
+        };
+        copy.completed = true;
+        items.insert(0, copy);
+
+        let (exported, _) = to_json(&items);
+        let tasks = serde_json::from_str::<Value>(&exported).unwrap();
+        assert_eq!(tasks[1]["uuid"], "8f1c7f4e-7c0a-4f0e-9b2a-1d2c3e4f5a6b");
+        assert_eq!(tasks[1]["status"], "waiting");
+        assert_ne!(tasks[0]["uuid"], tasks[1]["uuid"]);
+        assert_eq!(tasks[0]["status"], "completed");
+    }
+}
@@ -0,0 +1,178 @@
+use std::{iter, process::Command, thread};
+
+use fltk::enums::Color;
+use fltk_theme::{SchemeType, ThemeType};
+
+use crate::{constants::SYSTEM_THEME_POLL, i18n::tr};
+
+/// Theme setting that picks a light or dark theme from the desktop preference
+pub const SYSTEM_THEME: &str = "Follow system";
+/// Themes used when following the system
+const SYSTEM_LIGHT_THEME: ThemeType = ThemeType::Greybird;
+const SYSTEM_DARK_THEME: ThemeType = ThemeType::Dark;
+
+pub const THEMES: [(&str, ThemeType); 8] = [
+    ("Classic", ThemeType::Classic),
+    ("Aero", ThemeType::Aero),
+    ("Metro", ThemeType::Metro),
+    ("Aqua Classic", ThemeType::AquaClassic),
+    ("Greybird", ThemeType::Greybird),
+    ("Blue", ThemeType::Blue),
+    ("Dark", ThemeType::Dark),
+    ("High Contrast", ThemeType::HighContrast),
+];
+
+pub const SCHEMES: [(&str, SchemeType); 6] = [
+    ("Aqua", SchemeType::Aqua),
+    ("Clean", SchemeType::Clean),
+    ("Crystal", SchemeType::Crystal),
+    ("Fluent", SchemeType::Fluent),
+    ("Gleam", SchemeType::Gleam),
+    ("SVG based", SchemeType::SvgBased),
+];
+
+/// Name of a theme to show, the other themes being known by their name in any language
+pub fn theme_label(name: &str) -> String {
+    match name {
+        SYSTEM_THEME => tr("theme-follow-system"),
+        name => name.to_string(),
+    }
+}
+
+/// Names accepted by the theme setting, following the system first
+pub fn theme_names() -> impl Iterator<Item = &'static str> {
+    iter::once(SYSTEM_THEME).chain(THEMES.iter().map(|(name, _)| *name))
+}
+
+/// Theme matching a theme setting, asking the desktop when following the system
+pub fn resolve_theme(name: &str) -> ThemeType {
+    match THEMES.iter().find(|(theme, _)| *theme == name) {
+        Some((_, theme)) => *theme,
+        None if name == SYSTEM_THEME && system_prefers_dark() => SYSTEM_DARK_THEME,
+        None if name == SYSTEM_THEME => SYSTEM_LIGHT_THEME,
+        None => SYSTEM_DARK_THEME,
+    }
+}
+
+/// Scheme matching a scheme setting
+pub fn resolve_scheme(name: &str) -> Option<SchemeType> {
+    SCHEMES
+        .iter()
+        .find(|(scheme, _)| *scheme == name)
+        .map(|(_, scheme)| *scheme)
+}
+
+/// Colours of what the app draws itself, readable on the theme background
+#[derive(Clone, Copy, PartialEq)]
+pub struct Palette {
+    /// Column titles of the list
+    pub header: Color,
+    /// Secondary text such as the quit hint
+    pub hint: Color,
+}
+
+impl Palette {
+    pub fn new(theme: ThemeType) -> Self {
+        match theme {
+            ThemeType::Dark | ThemeType::HighContrast => Self {
+                header: Color::by_index(221),
+                hint: Color::by_index(52),
+            },
+            _ => Self {
+                header: Color::DarkBlue,
+                hint: Color::Dark3,
+            },
+        }
+    }
+}
+
+/// Call `changed` each time the desktop switches between light and dark,
+/// from a thread of its own as asking the desktop runs a command
+pub fn watch_system_theme(changed: impl Fn() + Send + 'static) {
+    thread::spawn(move || {
+        let mut dark = system_prefers_dark();
+        loop {
+            thread::sleep(SYSTEM_THEME_POLL);
+            let now_dark = system_prefers_dark();
+            if now_dark != dark {
+                dark = now_dark;
+                changed();
+            }
+        }
+    });
+}
+
+/// Whether the desktop asks applications for a dark colour scheme
+fn system_prefers_dark() -> bool {
+    if cfg!(target_os = "macos") {
+        command_output("defaults", &["read", "-g", "AppleInterfaceStyle"])
+            .is_some_and(|style| style.trim() == "Dark")
+    } else if cfg!(target_os = "windows") {
+        command_output(
+            "reg",
+            &[
+                "query",
+                r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+                "/v",
+                "AppsUseLightTheme",
+            ],
+        )
+        .is_some_and(|output| output.contains("0x0"))
+    } else {
+        // GNOME 42+ and most other desktops through the freedesktop setting,
+        // older ones only through the name of the GTK theme
+        match command_output(
+            "gsettings",
+            &["get", "org.gnome.desktop.interface", "color-scheme"],
+        ) {
+            Some(scheme) if scheme.contains("prefer-dark") => true,
+            Some(scheme) if scheme.contains("prefer-light") => false,
+            _ => command_output(
+                "gsettings",
+                &["get", "org.gnome.desktop.interface", "gtk-theme"],
+            )
+            .is_some_and(|theme| theme.to_lowercase().contains("dark")),
+        }
+    }
+}
+
+/// Standard output of a command, `None` if it cannot run or fails
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_are_found_by_name() {
+        assert!(matches!(resolve_theme("Greybird"), ThemeType::Greybird));
+        assert!(matches!(
+            resolve_theme("High Contrast"),
+            ThemeType::HighContrast
+        ));
+        // Unknown names, like ones from a newer version, fall back to dark
+        assert!(matches!(resolve_theme("Solarized"), ThemeType::Dark));
+        assert!(matches!(resolve_theme("greybird"), ThemeType::Dark));
+    }
+
+    #[test]
+    fn schemes_are_found_by_name() {
+        assert!(matches!(resolve_scheme("Gleam"), Some(SchemeType::Gleam)));
+        assert!(resolve_scheme("Plastic").is_none());
+        assert!(resolve_scheme("").is_none());
+    }
+
+    #[test]
+    fn following_the_system_comes_first() {
+        let names = theme_names().collect::<Vec<&str>>();
+        assert_eq!(names.len(), THEMES.len() + 1);
+        assert_eq!(names[0], SYSTEM_THEME);
+    }
+}
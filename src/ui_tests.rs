@@ -0,0 +1,400 @@
+//! Tests driving the main window like a user would, from the widgets to the data file.
+//! They need an X display: without one, each test runs again in a process of its own
+//! on the display of an Xvfb server, and is skipped if Xvfb cannot start
+
+use std::{
+    env, fs,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    process::{self, ChildStdin, Command, Stdio},
+    sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+    thread,
+};
+
+use chrono::DateTime;
+use fltk::{
+    app,
+    button::Button,
+    draw,
+    enums::Font,
+    prelude::{ButtonExt, ImageExt, InputExt, WidgetBase, WidgetExt},
+    window::Window,
+};
+use todolist_core::{update, ListItem, TodoList};
+
+use crate::{
+    config::Config,
+    controller::{Files, TodolistRS},
+    instance::Instance,
+    table::{Row, DESCRIPTION_COLUMN},
+    utils::{handle_message, Message},
+};
+
+/// Rounds of the event loop without a message after which the window is settled
+const IDLE_ROUNDS: usize = 3;
+
+/// One window at a time: FLTK has a single event loop
+static UI: Mutex<()> = Mutex::new(());
+
+/// Display of an Xvfb server started for the tests, `None` if it could not start.
+/// Xvfb is stopped when the test process exits, closing the pipe its shell waits on
+fn xvfb_display() -> Option<&'static str> {
+    static XVFB: OnceLock<Option<(String, ChildStdin)>> = OnceLock::new();
+
+    let xvfb = XVFB.get_or_init(|| {
+        // Xvfb prints the number of the display it picked, and is the only one left
+        // writing to the output, so that it ends if Xvfb does not start
+        let mut shell = Command::new("sh")
+            .arg("-c")
+            .arg(
+                "Xvfb -displayfd 1 -screen 0 1280x800x24 -nolisten tcp </dev/null 2>/dev/null & \
+                 exec >/dev/null; cat; kill $!",
+            )
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut display = String::new();
+        BufReader::new(shell.stdout.take()?)
+            .read_line(&mut display)
+            .ok()?;
+        let number = display.trim().parse::<u32>().ok()?;
+        Some((format!(":{number}"), shell.stdin.take()?))
+    });
+    xvfb.as_ref().map(|(display, _)| display.as_str())
+}
+
+/// Run the current test again in a process of its own on the display of Xvfb,
+/// as FLTK reads the display from the environment
+fn run_on_xvfb() {
+    let Some(display) = xvfb_display() else {
+        eprintln!("skipped: no display, and Xvfb could not start one");
+        return;
+    };
+    let current = thread::current();
+    let test = current
+        .name()
+        .expect("tests run on threads named after them");
+    let output = Command::new(env::current_exe().unwrap())
+        .args([test, "--exact", "--test-threads=1"])
+        .env("DISPLAY", display)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success() && stdout.contains("1 passed"),
+        "{stdout}{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Pending items with these descriptions, top first
+fn items(descriptions: &[&str]) -> Vec<ListItem> {
+    descriptions
+        .iter()
+        .enumerate()
+        .map(|(i, description)| ListItem {
+            description: description.to_string(),
+            created_at: DateTime::from_timestamp(1_704_067_200 + i as i64, 0).unwrap(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// The app started with the default preferences on files of an empty directory of its own
+struct Harness {
+    app: TodolistRS,
+    dir: PathBuf,
+    _ui: MutexGuard<'static, ()>,
+}
+
+impl Harness {
+    /// Start the app on a data file holding the items.
+    /// `None` without a display, the test having been run on Xvfb instead
+    fn launch(name: &str, items: Vec<ListItem>) -> Option<Self> {
+        if env::var_os("DISPLAY").is_none() {
+            run_on_xvfb();
+            return None;
+        }
+        let ui = UI.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let dir = env::temp_dir().join(format!("todolist-ui-{}-{name}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = Config {
+            data_path: dir.join("todo.dat").to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let files = Files {
+            config: Some(dir.join("config.toml")),
+            sessions: Some(dir.join("sessions.json")),
+        };
+        if !items.is_empty() {
+            TodoList::new(items).save(&config.data_path).unwrap();
+        }
+        let instance = Instance::acquire(&config.data_path, &[]).unwrap().unwrap();
+
+        let mut harness = Self {
+            app: TodolistRS::new(config, files, instance),
+            dir,
+            _ui: ui,
+        };
+        harness.settle();
+        Some(harness)
+    }
+
+    /// Handle the messages sent so far, as the event loop of the app would
+    fn settle(&mut self) {
+        let mut idle = 0;
+        while idle < IDLE_ROUNDS {
+            app::wait_for(0.01).unwrap();
+            match self.app.r.recv() {
+                Some(message) => {
+                    handle_message(&mut self.app, message);
+                    idle = 0;
+                }
+                None => idle += 1,
+            }
+        }
+    }
+
+    /// Send a message, as a key binding or a menu would
+    fn send(&mut self, message: Message) {
+        self.app.s.send(message);
+        self.settle();
+    }
+
+    fn click(&mut self, mut button: Button) {
+        button.do_callback();
+        self.settle();
+    }
+
+    fn type_description(&mut self, text: &str) {
+        self.app.m_window.description_input.set_value(text);
+    }
+
+    fn type_filter(&mut self, text: &str) {
+        let filter_input = &mut self.app.m_window.filter_input;
+        filter_input.set_value(text);
+        filter_input.do_callback();
+        self.settle();
+    }
+
+    /// Select the rows, as a click then Ctrl+clicks would, the last one going to the form
+    fn select_rows(&mut self, rows: &[i32]) {
+        let current = rows.last().copied();
+        self.app.m_window.list_table.set_selection(rows, current);
+        self.send(Message::Select);
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        self.app.m_window.list_table.rows()
+    }
+
+    fn descriptions(&self) -> Vec<String> {
+        self.rows().into_iter().map(|row| row.description).collect()
+    }
+
+    /// Columns of a cell, from its left, drawn in another colour than its background
+    fn drawn_columns(&self, row: i32, col: i32) -> Vec<i32> {
+        let image = self.app.m_window.list_table.cell_image(row, col).unwrap();
+        let (w, h) = (image.data_w(), image.data_h());
+        let data = image.to_rgb_data();
+        let pixel = |x: i32, y: i32| {
+            let start = ((y * w + x) * 3) as usize;
+            &data[start..start + 3]
+        };
+        let background = pixel(0, 0);
+        (0..w)
+            .filter(|&x| (0..h).any(|y| pixel(x, y) != background))
+            .collect()
+    }
+
+    fn selected_rows(&mut self) -> Vec<i32> {
+        self.app.m_window.list_table.selected_rows()
+    }
+
+    fn data_path(&self) -> &str {
+        &self.app.config.data_path
+    }
+
+    /// Items of the data file
+    fn saved(&self) -> Vec<ListItem> {
+        TodoList::load(self.data_path()).unwrap().into_items()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.app.wind.hide();
+        Window::delete(self.app.wind.clone());
+        self.settle();
+        fs::remove_dir_all(&self.dir).unwrap_or_default();
+    }
+}
+
+#[test]
+fn created_items_are_shown_and_saved() {
+    let Some(mut ui) = Harness::launch("create", vec![]) else {
+        return;
+    };
+    ui.type_description("buy milk");
+    ui.click(ui.app.m_window.create_button.clone());
+    assert_eq!(ui.descriptions(), ["buy milk"]);
+    assert_eq!(ui.app.m_window.description_input.value(), "");
+
+    // The description is drawn as wide as its text, a few pixels from the border
+    let drawn = ui.drawn_columns(0, DESCRIPTION_COLUMN);
+    draw::set_font(Font::Helvetica, ui.app.config.font_size);
+    let text_width = draw::width("buy milk").round() as i32;
+    let (first, last) = (drawn[0], drawn[drawn.len() - 1]);
+    assert!((1..10).contains(&first), "drawn from {first}");
+    assert!(
+        (last - first + 1 - text_width).abs() <= 3,
+        "drawn to {last}"
+    );
+
+    let saved = ui.saved();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].description, "buy milk");
+    assert!(!saved[0].completed);
+    let created = ui.app.config.date_format().format(saved[0].created_at);
+    assert_eq!(ui.rows()[0].datetime, created);
+}
+
+#[test]
+fn blank_descriptions_are_not_created() {
+    let Some(mut ui) = Harness::launch("blank", vec![]) else {
+        return;
+    };
+    ui.type_description("  \t");
+    ui.click(ui.app.m_window.create_button.clone());
+    assert!(ui.rows().is_empty());
+    assert!(ui.saved().is_empty());
+}
+
+#[test]
+fn selected_rows_are_shown_in_the_form_and_updated() {
+    let Some(mut ui) = Harness::launch("update", items(&["a", "b"])) else {
+        return;
+    };
+    ui.select_rows(&[1]);
+    let m_window = &ui.app.m_window;
+    assert_eq!(m_window.description_input.value(), "b");
+    assert!(m_window.description_input.readonly());
+    assert!(!m_window.create_button.active());
+    assert!(m_window.update_button.active());
+
+    ui.app.m_window.completed_input.set_value(true);
+    ui.click(ui.app.m_window.update_button.clone());
+    assert!(ui.rows()[1].completed);
+    assert!(ui.saved()[1].completed);
+    assert!(!ui.saved()[0].completed);
+    assert_eq!(ui.selected_rows(), [1]);
+}
+
+#[test]
+fn deleting_removes_the_selected_rows() {
+    let Some(mut ui) = Harness::launch("delete", items(&["a", "b", "c"])) else {
+        return;
+    };
+    ui.select_rows(&[0, 2]);
+    ui.click(ui.app.m_window.delete_button.clone());
+    assert_eq!(ui.descriptions(), ["b"]);
+    let saved = ui.saved().into_iter().map(|item| item.description);
+    assert_eq!(saved.collect::<Vec<String>>(), ["b"]);
+    assert!(ui.selected_rows().is_empty());
+    assert!(ui.app.m_window.create_button.active());
+}
+
+#[test]
+fn deleted_rows_are_restored_from_the_trash() {
+    let Some(mut ui) = Harness::launch("trash", items(&["a", "b", "c"])) else {
+        return;
+    };
+    ui.select_rows(&[1]);
+    ui.click(ui.app.m_window.delete_button.clone());
+    let trashed = ui.app.state.todo_list.trash()[0].item.id;
+    assert_eq!(TodoList::load(ui.data_path()).unwrap().trash().len(), 1);
+
+    ui.send(Message::List(update::Message::Restore(vec![trashed])));
+    assert_eq!(ui.descriptions(), ["b", "a", "c"]);
+    assert_eq!(ui.selected_rows(), [0]);
+    assert!(TodoList::load(ui.data_path()).unwrap().trash().is_empty());
+}
+
+#[test]
+fn completed_rows_are_archived_apart_from_the_list() {
+    let Some(mut ui) = Harness::launch("archive", items(&["a", "b"])) else {
+        return;
+    };
+    ui.select_rows(&[0, 1]);
+    ui.send(Message::ToggleCompleted);
+    ui.select_rows(&[1]);
+    ui.send(Message::ToggleCompleted);
+    ui.select_rows(&[0, 1]);
+    ui.send(Message::Archive);
+    assert_eq!(ui.descriptions(), ["b"]);
+
+    let mut saved = TodoList::load(ui.data_path()).unwrap();
+    assert!(saved.archived().is_empty());
+    saved.load_archive(ui.data_path()).unwrap();
+    let archived = saved.archived().iter().map(|archived| &archived.item);
+    let archived = archived.map(|item| item.description.as_str());
+    assert_eq!(archived.collect::<Vec<&str>>(), ["a"]);
+}
+
+#[test]
+fn filtered_rows_are_toggled_and_undone() {
+    let items = items(&["buy milk", "call Bob", "buy bread"]);
+    let Some(mut ui) = Harness::launch("filter", items) else {
+        return;
+    };
+    ui.type_filter("BUY");
+    assert_eq!(ui.descriptions(), ["buy milk", "buy bread"]);
+
+    ui.select_rows(&[0, 1]);
+    ui.send(Message::ToggleCompleted);
+    assert!(ui.rows().iter().all(|row| row.completed));
+    assert_eq!(ui.selected_rows(), [0, 1]);
+    let completed = ui.saved().into_iter().map(|item| item.completed);
+    assert_eq!(completed.collect::<Vec<bool>>(), [true, false, true]);
+
+    ui.send(Message::List(update::Message::Undo));
+    assert!(ui.rows().iter().all(|row| !row.completed));
+    assert!(ui.saved().iter().all(|item| !item.completed));
+}
+
+#[test]
+fn arrows_move_the_selection() {
+    let Some(mut ui) = Harness::launch("arrows", items(&["a", "b", "c"])) else {
+        return;
+    };
+    ui.send(Message::List(update::Message::SelectNext));
+    ui.send(Message::List(update::Message::SelectNext));
+    assert_eq!(ui.selected_rows(), [1]);
+    assert_eq!(ui.app.m_window.description_input.value(), "b");
+    ui.send(Message::List(update::Message::SelectPrevious));
+    ui.send(Message::List(update::Message::SelectPrevious));
+    assert_eq!(ui.selected_rows(), [0]);
+}
+
+#[test]
+fn later_launches_add_to_the_open_window() {
+    let Some(mut ui) = Harness::launch("forwarded", items(&["a"])) else {
+        return;
+    };
+    let args = ["add", "buy milk"].map(String::from);
+    assert!(Instance::acquire(ui.data_path(), &args).unwrap().is_none());
+
+    // Received on the thread listening to the socket, which may not have sent them yet
+    for _ in 0..100 {
+        ui.settle();
+        if ui.descriptions().len() > 1 {
+            break;
+        }
+    }
+    assert_eq!(ui.descriptions(), ["buy milk", "a"]);
+    let saved = ui.saved().into_iter().map(|item| item.description);
+    assert_eq!(saved.collect::<Vec<String>>(), ["buy milk", "a"]);
+}
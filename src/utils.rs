@@ -1,253 +1,1564 @@
-use std::fs;
+use std::{collections::HashMap, fs, mem, path::Path, process};
 
-use chrono::{DateTime, Local};
+use chrono::{Duration, Utc};
 use fltk::{
+    app,
     app::Sender,
-    browser::HoldBrowser,
     button::{Button, CheckButton},
-    enums::{CallbackTrigger, Color},
+    dialog, draw,
+    enums::{Align, CallbackTrigger, Shortcut},
     frame,
+    group::{Flex, FlexType},
     input::Input,
-    prelude::{BrowserExt, ButtonExt, InputExt, WidgetExt},
+    menu::{Choice, MenuFlag, MenuItem, SysMenuBar},
+    prelude::{ButtonExt, GroupExt, InputExt, MenuExt, WidgetBase, WidgetExt},
 };
 use fltk_theme::widget_themes;
 use resolve_path::PathResolveExt;
 
 use crate::{
-    constants::{DATA_PATH, WIDGET_HEIGHT, WIDGET_PADDING, WIDGET_WIDTH},
+    config::{dump_config, Config},
+    constants::{
+        FORM_HEIGHT, FORM_WIDTH, NARROW_WIDTH, WIDGET_HEIGHT, WIDGET_PADDING, WIDGET_WIDTH,
+    },
     controller::TodolistRS,
-    models::ListItem,
+    datetime::DateFormat,
+    dialogs::{
+        archive_dialog, batch_preview_dialog, choose_open_file, choose_option, choose_save_file,
+        inline_edit, paste_text_dialog, preferences_dialog, shortcuts_overlay, text_dialog,
+        trash_dialog, TrashAction,
+    },
+    i18n::{room_for, tr, tr_args},
+    instance::{parse_args, Command, Instance},
+    keymap::Keymap,
+    markdown,
+    orgmode::{self, Nesting},
+    report::{Grouping, Report, GROUPINGS},
+    session::{dump_session, Session},
+    table::{display_text, Row, TodoTable, COMPLETED_COLUMN, DATETIME_COLUMN, DESCRIPTION_COLUMN},
+    taskwarrior,
+    theme::{theme_label, theme_names, Palette, SCHEMES, SYSTEM_THEME},
+};
+use todolist_core::{
+    update::{self, Effect, State},
+    ArchivedItem, Change, Event, ItemId, ListItem, SortOrder, StorageError, TodoList, TrashedItem,
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone, PartialEq)]
 pub enum Message {
+    /// Change of the list, the rows shown or the selection, applied by `update::update`
+    List(update::Message),
     Create,
     Update,
     Delete,
+    /// The selection of the table changed
     Select,
-    Filter,
+    ExportMarkdown,
+    CopyMarkdown,
+    ImportMarkdown,
+    PasteMarkdown,
+    ExportOrg,
+    ImportOrg,
+    ExportTaskwarrior,
+    ImportTaskwarrior,
+    ExportReport,
+    PrintReport,
+    PasteBatch,
+    Preferences,
+    NewDataFile,
+    OpenDataFile,
+    Quit,
+    NewTodo,
+    ToggleCompleted,
+    Edit,
+    Duplicate,
+    CopyText,
+    /// Timeline of the focused to-do, from the journal
+    History,
+    /// Deleted to-dos, to restore them or empty the trash
+    Trash,
+    /// Move the selected completed to-dos to the archive
+    Archive,
+    /// Archived to-dos, to search them and put them back
+    ShowArchive,
+    /// Menu of actions on the selected rows, at the focused row
+    ContextMenu,
+    /// Same menu at the mouse pointer, after a right click
+    ContextMenuAtPointer,
+    FocusFilter,
+    /// Theme picked in the View menu, by name
+    Theme(String),
+    /// Scheme picked in the View menu, by name
+    Scheme(String),
+    Shortcuts,
+    About,
+    /// The desktop switched between light and dark
+    SystemTheme,
+    /// Redraw the dates, which read differently as time goes by or the time zone changes
+    RefreshDates,
+    /// The header of this column of the list was clicked
+    SortColumn(i32),
+    /// Commands of the command line of this launch or of a later one
+    Commands(Vec<Command>),
+    /// Arguments of a later launch
+    Forwarded(Vec<String>),
 }
 
+#[derive(Clone)]
 pub struct MainWindow {
+    pub body: Flex,
     pub completed_input: CheckButton,
     pub create_button: Button,
     pub delete_button: Button,
     pub description_input: Input,
     pub filter_input: Input,
-    pub list_browser: HoldBrowser,
+    pub form: Flex,
+    pub hint: frame::Frame,
+    pub list_table: TodoTable,
+    pub menu_bar: SysMenuBar,
+    pub sort_choice: Choice,
     pub update_button: Button,
 }
 
-/// Save the data to the storage file
-fn dump_list_items(model: &Vec<ListItem>) {
-    fs::write(DATA_PATH.resolve(), rmp_serde::to_vec(model).unwrap())
+/// Save the list to the data file, and its changes to the journal
+fn dump_list_items(todo_list: &mut TodoList, path: &str) {
+    todo_list
+        .save(path)
         .map_err(|err| eprintln!("{err:?}"))
         .unwrap_or_default();
 }
 
-/// Gets the current date and time
-fn get_datetime() -> String {
-    let current_local: DateTime<Local> = Local::now();
-    current_local.format("%d-%m-%Y • %H:%M:%S").to_string()
+/// Save the list now, or only remember to do it on exit when autosave is off
+fn save_list_items(todo_list: &mut TodoList, config: &Config, unsaved: &mut bool) {
+    match config.autosave {
+        true => dump_list_items(todo_list, &config.data_path),
+        false => *unsaved = true,
+    }
 }
 
-/// Load data from storage file
-fn load_list_items() -> Vec<ListItem> {
-    let data: Vec<u8> = fs::read(DATA_PATH.resolve())
-        .map_err(|err| eprintln!("{err:?}"))
-        .unwrap_or_default();
-    // Bail since we found no data.
-    if data.is_empty() {
-        return vec![];
+/// Load the list of the data file, dropping items deleted more than `trash_days` ago
+/// and archiving those completed more than `archive_days` ago, without their archive.
+/// A damaged one is rebuilt from its journal, if there is one.
+/// Fails on any other error, so that a file that cannot be read is never saved over
+fn load_todo_list(config: &Config) -> Result<TodoList, StorageError> {
+    let path = config.data_path.as_str();
+    let mut todo_list = match TodoList::load(path) {
+        Ok(todo_list) => todo_list,
+        Err(err @ StorageError::Decode(_)) => match TodoList::rebuild(path) {
+            Ok(todo_list) if !todo_list.is_empty() => {
+                eprintln!("{path}: {err}, rebuilt from the journal");
+                todo_list
+            }
+            _ => return Err(err),
+        },
+        Err(err) => return Err(err),
+    };
+    if config.trash_days > 0 {
+        todo_list.purge_trash(Utc::now() - Duration::days(config.trash_days.into()));
+    }
+    if config.archive_days > 0 {
+        let before = Utc::now() - Duration::days(config.archive_days.into());
+        let indices = todo_list.completed_before(before);
+        if !indices.is_empty() {
+            todo_list
+                .load_archive(path)
+                .and_then(|()| match todo_list.archive(&indices) {
+                    0 => Ok(()),
+                    _ => todo_list.save(path),
+                })
+                .map_err(|err| eprintln!("{err:?}"))
+                .unwrap_or_default();
+        }
+    }
+    // Without the history of the archiving, nor the archive
+    let (items, trash) = todo_list.into_parts();
+    Ok(TodoList::with_trash(items, trash))
+}
+
+/// Send the arguments of later launches to the window
+pub fn listen(instance: &Instance, s: &Sender<Message>) {
+    let s = s.clone();
+    instance.listen(move |args| s.send(Message::Forwarded(args)));
+}
+
+/// Lock another data file for the window, which keeps the current lock until it takes this one.
+/// Returns `None`, after telling the user, if another window has it, which is raised
+fn lock_data_file(path: &str, s: &Sender<Message>) -> Option<Instance> {
+    match Instance::acquire(path, &[]) {
+        Ok(Some(instance)) => {
+            listen(&instance, s);
+            Some(instance)
+        }
+        Ok(None) => {
+            dialog::alert_default(&tr_args("alert-data-file-open", &[("path", &path)]));
+            None
+        }
+        Err(err) => {
+            eprintln!("{err:?}");
+            dialog::alert_default(&tr_args(
+                "alert-lock-data-file",
+                &[("path", &path), ("error", &err)],
+            ));
+            None
+        }
+    }
+}
+
+/// Message replacing the list with the one of the data file.
+/// Returns `None`, after telling the user, if it cannot be loaded
+fn load_message(config: &Config) -> Option<update::Message> {
+    match load_todo_list(config) {
+        Ok(todo_list) => {
+            let (items, trash) = todo_list.into_parts();
+            Some(update::Message::Load { items, trash })
+        }
+        Err(err) => {
+            eprintln!("{err:?}");
+            dialog::alert_default(&tr_args(
+                "alert-read-file",
+                &[("path", &config.data_path), ("error", &err)],
+            ));
+            None
+        }
+    }
+}
+
+/// Read the archive of the data file, if it is not read yet.
+/// Returns `false`, after telling the user, if it cannot be read
+fn load_archive(todo_list: &mut TodoList, path: &str) -> bool {
+    match todo_list.load_archive(path) {
+        Ok(()) => true,
+        Err(err) => {
+            dialog::alert_default(&tr_args("alert-read-archive", &[("error", &err)]));
+            false
+        }
+    }
+}
+
+/// Lines of the archive dialog: when each item was archived, and its description
+fn archive_lines(archive: &[ArchivedItem], date_format: &DateFormat) -> Vec<String> {
+    archive
+        .iter()
+        .map(|archived| {
+            format!(
+                "{}  {}",
+                date_format.format(archived.archived_at),
+                display_text(&archived.item.description)
+            )
+        })
+        .collect()
+}
+
+/// Lines of the trash dialog: when each item was deleted, and its description
+fn trash_lines(trash: &[TrashedItem], date_format: &DateFormat) -> Vec<String> {
+    trash
+        .iter()
+        .map(|trashed| {
+            format!(
+                "{}  {}",
+                date_format.format(trashed.deleted_at),
+                display_text(&trashed.item.description)
+            )
+        })
+        .collect()
+}
+
+/// Lines of the timeline of an item, oldest first
+fn timeline_text(events: &[Event], date_format: &DateFormat) -> String {
+    events
+        .iter()
+        .map(|event| {
+            let at = date_format.format(event.at);
+            let what = match &event.change {
+                Change::Created { .. } => tr("event-created"),
+                Change::Renamed { description } => tr_args(
+                    "event-renamed",
+                    &[("description", &display_text(description))],
+                ),
+                Change::Completed => tr("event-completed"),
+                Change::Uncompleted => tr("event-uncompleted"),
+                Change::Deleted => tr("event-deleted"),
+                Change::Restored { .. } => tr("event-restored"),
+                Change::Edited { .. } => tr("event-edited"),
+                Change::Archived => tr("event-archived"),
+                Change::Unarchived { .. } => tr("event-unarchived"),
+                Change::Purged => tr("event-purged"),
+            };
+            format!("{at}  {what}")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Identifiers of the items shown in these rows of the table
+fn item_ids(todo_list: &TodoList, visible: &[usize], rows: &[i32]) -> Vec<ItemId> {
+    rows.iter()
+        .map(|&row| todo_list[visible[row as usize]].id)
+        .collect()
+}
+
+/// Row of the table showing the item
+fn row_of(visible: &[usize], index: usize) -> Option<i32> {
+    visible
+        .iter()
+        .position(|&shown| shown == index)
+        .map(|row| row as i32)
+}
+
+/// Write text to a file chosen by the user, reporting failures
+fn write_text_file(path: &Path, text: &str) {
+    if let Err(err) = fs::write(path, text) {
+        eprintln!("{err:?}");
+        dialog::alert_default(&tr_args(
+            "alert-write-file",
+            &[("path", &path.display()), ("error", &err)],
+        ));
+    }
+}
+
+/// Read text from a file chosen by the user, reporting failures
+fn read_text_file(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .map_err(|err| {
+            eprintln!("{err:?}");
+            dialog::alert_default(&tr_args(
+                "alert-read-file",
+                &[("path", &path.display()), ("error", &err)],
+            ));
+        })
+        .ok()
+}
+
+/// Text for labels, tooltips and message boxes, which would read `@` as a symbol
+fn escape_label(text: &str) -> String {
+    text.replace('@', "@@")
+}
+
+/// Path of a menu item, from the message ids of the menu and the item like `menu-file/menu-quit`
+fn menu_path(ids: &str) -> String {
+    ids.split('/').map(tr).collect::<Vec<String>>().join("/")
+}
+
+/// Path of the item of a sort order in the View menu
+fn sort_menu_path(sort_order: SortOrder) -> String {
+    format!(
+        "{}/{}",
+        menu_path("menu-view/menu-sort"),
+        sort_order_label(sort_order)
+    )
+}
+
+/// Paths of the items of a theme and of a scheme in the View menu
+fn theme_menu_path(name: &str) -> String {
+    format!(
+        "{}/{}",
+        menu_path("menu-view/menu-theme"),
+        theme_label(name)
+    )
+}
+
+fn scheme_menu_path(name: &str) -> String {
+    format!("{}/{name}", menu_path("menu-view/menu-scheme"))
+}
+
+/// Name of a sort order in the current language
+fn sort_order_label(sort_order: SortOrder) -> String {
+    tr(match sort_order {
+        SortOrder::NewestFirst => "sort-newest-first",
+        SortOrder::OldestFirst => "sort-oldest-first",
+        SortOrder::Description => "sort-description",
+        SortOrder::PendingFirst => "sort-pending-first",
+    })
+}
+
+/// Frame showing a label for the widget that follows it
+fn side_label(label: &str) -> frame::Frame {
+    frame::Frame::default()
+        .with_label(label)
+        .with_align(Align::Right | Align::Inside)
+}
+
+/// Give the labels drawn at the `from` font size the `to` size, in the whole widget tree.
+/// Labels with a size of their own are left alone
+fn set_label_sizes(group: &impl GroupExt, from: i32, to: i32) {
+    for i in 0..group.children() {
+        let Some(mut child) = group.child(i) else {
+            continue;
+        };
+        if child.label_size() == from {
+            child.set_label_size(to);
+        }
+        if let Some(child) = child.as_group() {
+            set_label_sizes(&child, from, to);
+        }
     }
+}
+
+/// Give the quit hint the colour of the palette
+/// and tick the current theme and scheme in the View menu
+pub fn show_theme(
+    hint: &mut frame::Frame,
+    menu_bar: &mut SysMenuBar,
+    config: &Config,
+    palette: Palette,
+) {
+    hint.set_label_color(palette.hint);
+    hint.redraw_label();
+
+    let names = theme_names()
+        .map(|name| (theme_menu_path(name), name == config.theme))
+        .chain(
+            SCHEMES
+                .iter()
+                .map(|(name, _)| (scheme_menu_path(name), *name == config.scheme)),
+        );
+    tick_menu_items(menu_bar, names);
+}
 
-    rmp_serde::from_slice::<Vec<ListItem>>(&data).unwrap()
+/// Tick the sort order in the View menu
+fn show_sort_order(menu_bar: &mut SysMenuBar, sort_order: SortOrder) {
+    let names = SortOrder::ALL
+        .iter()
+        .map(|&order| (sort_menu_path(order), order == sort_order));
+    tick_menu_items(menu_bar, names);
+}
+
+/// Set or clear radio items of the menu bar, given by path
+fn tick_menu_items(menu_bar: &mut SysMenuBar, items: impl Iterator<Item = (String, bool)>) {
+    for (path, current) in items {
+        if let Some(mut item) = menu_bar.find_item(&path) {
+            match current {
+                true => item.set(),
+                false => item.clear(),
+            }
+        }
+    }
+}
+
+/// Menu entries other than the radio items of the View menu, as paths of message ids.
+/// Their accelerators come from the keymap
+fn menu_entries() -> Vec<(&'static str, MenuFlag, Message)> {
+    let normal = MenuFlag::Normal;
+    let divider = MenuFlag::MenuDivider;
+
+    vec![
+        ("menu-file/menu-new-data-file", normal, Message::NewDataFile),
+        (
+            "menu-file/menu-open-data-file",
+            divider,
+            Message::OpenDataFile,
+        ),
+        (
+            "menu-file/menu-import/menu-markdown",
+            normal,
+            Message::ImportMarkdown,
+        ),
+        (
+            "menu-file/menu-import/menu-paste-markdown",
+            normal,
+            Message::PasteMarkdown,
+        ),
+        ("menu-file/menu-import/menu-org", normal, Message::ImportOrg),
+        (
+            "menu-file/menu-import/menu-taskwarrior",
+            normal,
+            Message::ImportTaskwarrior,
+        ),
+        (
+            "menu-file/menu-export/menu-markdown",
+            normal,
+            Message::ExportMarkdown,
+        ),
+        (
+            "menu-file/menu-export/menu-copy-markdown",
+            normal,
+            Message::CopyMarkdown,
+        ),
+        ("menu-file/menu-export/menu-org", normal, Message::ExportOrg),
+        (
+            "menu-file/menu-export/menu-taskwarrior",
+            normal,
+            Message::ExportTaskwarrior,
+        ),
+        (
+            "menu-file/menu-export/menu-html-report",
+            normal,
+            Message::ExportReport,
+        ),
+        ("menu-file/menu-print-report", divider, Message::PrintReport),
+        ("menu-file/menu-preferences", divider, Message::Preferences),
+        ("menu-file/menu-quit", normal, Message::Quit),
+        (
+            "menu-edit/menu-undo",
+            normal,
+            Message::List(update::Message::Undo),
+        ),
+        (
+            "menu-edit/menu-redo",
+            divider,
+            Message::List(update::Message::Redo),
+        ),
+        ("menu-edit/menu-new-todo", normal, Message::NewTodo),
+        (
+            "menu-edit/menu-toggle-completed",
+            normal,
+            Message::ToggleCompleted,
+        ),
+        ("menu-edit/menu-edit-description", normal, Message::Edit),
+        ("menu-edit/menu-duplicate", normal, Message::Duplicate),
+        ("menu-edit/menu-copy-text", normal, Message::CopyText),
+        ("menu-edit/menu-history", normal, Message::History),
+        ("menu-edit/menu-archive-completed", normal, Message::Archive),
+        ("menu-edit/menu-trash", divider, Message::Trash),
+        (
+            "menu-edit/menu-select-all",
+            normal,
+            Message::List(update::Message::SelectAll),
+        ),
+        ("menu-edit/menu-delete", normal, Message::Delete),
+        ("menu-view/menu-filter", normal, Message::FocusFilter),
+        ("menu-view/menu-archive", divider, Message::ShowArchive),
+        ("menu-help/menu-shortcuts", normal, Message::Shortcuts),
+        ("menu-help/menu-about", normal, Message::About),
+    ]
+}
+
+/// Build the File, Edit, View and Help menus
+fn draw_menu_bar(sender: Sender<Message>, keymap: &Keymap) -> SysMenuBar {
+    let mut menu_bar = SysMenuBar::default();
+    for (path, flag, message) in menu_entries() {
+        menu_bar.add_emit(
+            &menu_path(path),
+            keymap.shortcut(&message),
+            flag,
+            sender.clone(),
+            message,
+        );
+    }
+
+    for sort_order in SortOrder::ALL {
+        menu_bar.add_emit(
+            &sort_menu_path(sort_order),
+            Shortcut::None,
+            MenuFlag::Radio,
+            sender.clone(),
+            Message::List(update::Message::SetSortOrder(sort_order)),
+        );
+    }
+    for name in theme_names() {
+        menu_bar.add_emit(
+            &theme_menu_path(name),
+            Shortcut::None,
+            match name == SYSTEM_THEME {
+                true => MenuFlag::Radio | MenuFlag::MenuDivider,
+                false => MenuFlag::Radio,
+            },
+            sender.clone(),
+            Message::Theme(name.to_string()),
+        );
+    }
+    for (name, _) in SCHEMES {
+        menu_bar.add_emit(
+            &scheme_menu_path(name),
+            Shortcut::None,
+            MenuFlag::Radio,
+            sender.clone(),
+            Message::Scheme(name.to_string()),
+        );
+    }
+
+    menu_bar
+}
+
+/// Column of the list the rows are sorted by, and whether in ascending order
+fn sorted_column(sort_order: SortOrder) -> (i32, bool) {
+    match sort_order {
+        SortOrder::NewestFirst => (DATETIME_COLUMN, false),
+        SortOrder::OldestFirst => (DATETIME_COLUMN, true),
+        SortOrder::Description => (DESCRIPTION_COLUMN, true),
+        SortOrder::PendingFirst => (COMPLETED_COLUMN, true),
+    }
 }
 
 /// Configure UI Items
-pub fn draw_ui(sender: Sender<Message>) -> MainWindow {
-    let mut filter_input = Input::default()
-        .with_size(WIDGET_WIDTH, WIDGET_HEIGHT)
-        .with_pos(WIDGET_PADDING + WIDGET_WIDTH * 2, WIDGET_PADDING)
-        .with_label("Filter prefix:");
-    filter_input.set_trigger(CallbackTrigger::Changed);
-    filter_input.emit(sender, Message::Filter);
-
-    let mut list_browser = HoldBrowser::default()
-        .with_pos(
-            WIDGET_PADDING,
-            filter_input.y() + filter_input.height() + WIDGET_PADDING,
-        )
-        .with_size(WIDGET_WIDTH * 7, WIDGET_HEIGHT * 4);
-    list_browser.set_column_widths(&[WIDGET_WIDTH * 2, WIDGET_WIDTH * 3, WIDGET_WIDTH]);
-    list_browser.set_column_char('\t');
-    list_browser.emit(sender, Message::Select);
-
-    let description_input = Input::default()
-        .with_size(WIDGET_WIDTH * 2, WIDGET_HEIGHT)
-        .with_pos(
-            list_browser.x() + list_browser.width() + WIDGET_PADDING * 3 + WIDGET_WIDTH,
-            list_browser.y(),
-        )
-        .with_label("Description:");
-
-    let label_completed = frame::Frame::default().with_label("Completed:").with_pos(
-        list_browser.x() + list_browser.width() + WIDGET_PADDING * 5,
-        list_browser.y() + list_browser.height() - WIDGET_PADDING,
+pub fn draw_ui(sender: Sender<Message>, keymap: &Keymap, palette: Palette) -> MainWindow {
+    let mut window_column = Flex::default_fill().column();
+    let menu_bar = draw_menu_bar(sender.clone(), keymap);
+    window_column.fixed(&menu_bar, WIDGET_HEIGHT);
+
+    let mut root = Flex::default().column();
+    root.set_margin(WIDGET_PADDING);
+    root.set_pad(WIDGET_PADDING);
+
+    let mut filter_row = Flex::default().row();
+    let filter_text = tr("label-filter-prefix");
+    let filter_label = side_label(&filter_text);
+    filter_row.fixed(
+        &filter_label,
+        room_for(&[&filter_text], WIDGET_WIDTH * 2 - WIDGET_PADDING),
     );
-    let completed_input = CheckButton::default().with_size(20, 20).with_pos(
-        label_completed.x() + label_completed.width() + WIDGET_PADDING * 5,
-        label_completed.y() - 10,
+    let mut filter_input = Input::default();
+    filter_input.set_trigger(CallbackTrigger::Changed);
+    filter_input.set_callback({
+        let sender = sender.clone();
+        move |input| sender.send(Message::List(update::Message::SetFilter(input.value())))
+    });
+    filter_row.fixed(&filter_input, WIDGET_WIDTH * 2);
+    frame::Frame::default();
+    let sort_text = tr("label-sort");
+    let sort_label = side_label(&sort_text);
+    filter_row.fixed(&sort_label, room_for(&[&sort_text], WIDGET_WIDTH));
+    let mut sort_choice = Choice::default();
+    for sort_order in SortOrder::ALL {
+        sort_choice.add_choice(&sort_order_label(sort_order));
+    }
+    sort_choice.set_value(0);
+    sort_choice.set_callback({
+        let sender = sender.clone();
+        move |choice| {
+            let sort_order = SortOrder::ALL[choice.value().max(0) as usize];
+            sender.send(Message::List(update::Message::SetSortOrder(sort_order)))
+        }
+    });
+    filter_row.fixed(&sort_choice, WIDGET_WIDTH * 2);
+    filter_row.end();
+    root.fixed(&filter_row, WIDGET_HEIGHT);
+
+    // List and form side by side, or stacked on narrow windows (see `fit_layout`)
+    let mut body = Flex::default().row();
+    body.set_pad(WIDGET_PADDING * 2);
+
+    let mut list_table = TodoTable::new(sender.clone(), palette);
+    list_table.set_column_widths(&[WIDGET_WIDTH * 3, WIDGET_WIDTH * 2, WIDGET_WIDTH]);
+
+    let mut form = Flex::default().column();
+    form.set_pad(WIDGET_PADDING);
+
+    let description_text = tr("label-description");
+    let completed_text = tr("label-completed");
+    let form_label_width = room_for(
+        &[&description_text, &completed_text],
+        WIDGET_WIDTH + WIDGET_PADDING,
     );
 
-    let mut create_button = Button::default()
-        .with_size(WIDGET_WIDTH, WIDGET_HEIGHT)
-        .with_pos(
-            WIDGET_PADDING,
-            list_browser.y() + list_browser.height() + WIDGET_PADDING,
-        )
-        .with_label("Create");
+    let mut description_row = Flex::default().row();
+    let description_label = side_label(&description_text);
+    description_row.fixed(&description_label, form_label_width);
+    let description_input = Input::default();
+    description_row.end();
+    form.fixed(&description_row, WIDGET_HEIGHT);
+
+    let mut completed_row = Flex::default().row();
+    let completed_label = side_label(&completed_text);
+    completed_row.fixed(&completed_label, form_label_width);
+    let completed_input = CheckButton::default();
+    completed_row.fixed(&completed_input, 20);
+    frame::Frame::default();
+    completed_row.end();
+    form.fixed(&completed_row, WIDGET_HEIGHT);
+
+    frame::Frame::default();
+    form.end();
+    body.fixed(&form, FORM_WIDTH);
+    body.end();
+
+    let mut buttons_row = Flex::default().row();
+    let [create_text, update_text, delete_text] =
+        ["button-create", "button-update", "button-delete"].map(tr);
+    let button_width = room_for(&[&create_text, &update_text, &delete_text], WIDGET_WIDTH);
+
+    let mut create_button = Button::default().with_label(&create_text);
     create_button.set_frame(widget_themes::OS_MINI_BUTTON_UP_BOX);
-    create_button.emit(sender, Message::Create);
+    create_button.emit(sender.clone(), Message::Create);
+    buttons_row.fixed(&create_button, button_width);
 
-    let mut update_button = Button::default()
-        .with_size(WIDGET_WIDTH, WIDGET_HEIGHT)
-        .right_of(&create_button, WIDGET_PADDING)
-        .with_label("Update");
+    let mut update_button = Button::default().with_label(&update_text);
     update_button.set_frame(widget_themes::OS_MINI_BUTTON_UP_BOX);
-    update_button.emit(sender, Message::Update);
+    update_button.emit(sender.clone(), Message::Update);
     update_button.deactivate();
+    buttons_row.fixed(&update_button, button_width);
 
-    let mut delete_button = Button::default()
-        .with_size(WIDGET_WIDTH, WIDGET_HEIGHT)
-        .right_of(&update_button, WIDGET_PADDING)
-        .with_label("Delete");
+    let mut delete_button = Button::default().with_label(&delete_text);
     delete_button.set_frame(widget_themes::OS_MINI_BUTTON_UP_BOX);
     delete_button.emit(sender, Message::Delete);
     delete_button.deactivate();
+    buttons_row.fixed(&delete_button, button_width);
 
-    // Set text info: "Quit the application by push 'Escape' key"
-    let mut label = frame::Frame::default().with_pos(
-        delete_button.x() + delete_button.width() + WIDGET_PADDING * 36,
-        delete_button.y() + WIDGET_PADDING,
-    );
-    label.set_label("Quit the application by push 'Escape' key");
-    label.set_label_color(Color::by_index(52));
-    label.set_label_size(12);
+    let mut hint = frame::Frame::default().with_align(Align::Right | Align::Inside | Align::Clip);
+    let shortcuts = keymap.shortcut(&Message::Shortcuts);
+    if shortcuts != Shortcut::None {
+        hint.set_label(&tr_args(
+            "hint-shortcuts",
+            &[("shortcut", &draw::shortcut_label(shortcuts))],
+        ));
+    }
+    hint.set_label_size(12);
+
+    buttons_row.end();
+    root.fixed(&buttons_row, WIDGET_HEIGHT);
+    root.end();
+    window_column.end();
 
     MainWindow {
+        body,
         completed_input,
         create_button,
         delete_button,
         description_input,
         filter_input,
-        list_browser,
+        form,
+        hint,
+        list_table,
+        menu_bar,
+        sort_choice,
         update_button,
     }
 }
 
-/// Create the message waiting loop
-/// and start the application
-pub fn message_waiting_loop(app: &mut TodolistRS) {
+/// Adapt the layout to the window width: the form goes under the list
+/// on narrow windows, and the table columns keep their proportions
+pub fn fit_layout(m_window: &mut MainWindow, width: i32) {
+    let MainWindow {
+        body,
+        form,
+        list_table,
+        ..
+    } = m_window;
+
+    if width < NARROW_WIDTH {
+        body.set_type(FlexType::Column);
+        body.fixed(form, FORM_HEIGHT);
+    } else {
+        body.set_type(FlexType::Row);
+        body.fixed(form, FORM_WIDTH);
+    }
+    body.layout();
+    list_table.fit_columns();
+}
+
+/// Rows of the table for the items at these indices
+fn table_rows(todo_list: &TodoList, visible: &[usize], date_format: &DateFormat) -> Vec<Row> {
+    visible
+        .iter()
+        .map(|&index| Row {
+            description: todo_list[index].description.clone(),
+            datetime: date_format.format(todo_list[index].created_at),
+            completed: todo_list[index].completed,
+            depth: todo_list[index].depth,
+        })
+        .collect()
+}
+
+/// Show the selection, the item in the form, the filter and the sort order of the state.
+/// `visible` holds the model indices of the rows of the table
+fn view(m_window: &mut MainWindow, state: &State, visible: &[usize], scroll: bool) {
     let MainWindow {
         completed_input,
         create_button,
         delete_button,
         description_input,
         filter_input,
-        list_browser,
+        list_table,
+        menu_bar,
+        sort_choice,
         update_button,
-    } = &mut app.m_window;
+        ..
+    } = m_window;
+
+    let rows_by_id = visible
+        .iter()
+        .enumerate()
+        .map(|(row, &index)| (state.todo_list[index].id, row as i32))
+        .collect::<HashMap<ItemId, i32>>();
+    let rows = state
+        .selection
+        .iter()
+        .filter_map(|id| rows_by_id.get(id).copied())
+        .collect::<Vec<i32>>();
+    let current = state.current.and_then(|id| rows_by_id.get(&id).copied());
+    list_table.set_selection(&rows, current);
+    if let (Some(row), true) = (current, scroll) {
+        list_table.make_visible(row);
+    }
+
+    match current {
+        None => {
+            create_button.activate();
+            // Keep what is being typed for a new item
+            if description_input.readonly() {
+                description_input.set_value("");
+                description_input.set_readonly(false);
+            }
+            description_input.set_tooltip("");
+            update_button.deactivate();
+            delete_button.deactivate();
+            completed_input.set_value(false);
+            completed_input.deactivate();
+        }
+        Some(row) => {
+            let item = &state.todo_list[visible[row as usize]];
+            create_button.deactivate();
+            completed_input.activate();
+            completed_input.set_value(item.completed);
+            description_input.set_value(&item.description);
+            description_input.set_readonly(true);
+            description_input.set_tooltip(&escape_label(&item.description));
+            update_button.activate();
+            delete_button.activate();
+        }
+    }
+
+    // Set only on change, not to move the cursor of the filter being typed
+    if filter_input.value() != state.filter {
+        filter_input.set_value(&state.filter);
+    }
+    if let Some(index) = SortOrder::ALL
+        .iter()
+        .position(|&sort_order| sort_order == state.sort_order)
+    {
+        sort_choice.set_value(index as i32);
+    }
+    show_sort_order(menu_bar, state.sort_order);
+    list_table.set_sorted_by(Some(sorted_column(state.sort_order)));
+}
 
-    let mut model = load_list_items();
+/// Load the list of the data file and show it as it was when the app was last closed
+pub fn restore_state(app: &mut TodolistRS) {
+    let todo_list = load_todo_list(&app.config).unwrap_or_else(|err| {
+        eprintln!("{err:?}");
+        dialog::alert_default(&tr_args(
+            "alert-read-file",
+            &[("path", &app.config.data_path), ("error", &err)],
+        ));
+        process::exit(1)
+    });
+    app.state = State {
+        todo_list,
+        filter: app.session.filter.clone(),
+        sort_order: app.session.sort_order,
+        ..Default::default()
+    };
+    app.visible = app.state.visible();
     // ↓↓ reverse vector ↓↓
     // .into_iter()
-    // .rev()load_list_items();
+    // .rev()load_todo_list();
     // .collect::<Vec<ListItem>>();
 
-    while app.a.wait() {
-        match app.r.recv() {
-            Some(Message::Create) => {
-                // Do not allow empty TO-DO
-                if description_input.value().trim() != "" {
-                    model.insert(
-                        0,
-                        ListItem {
-                            completed: false,
-                            description: description_input.value(),
-                            datetime: get_datetime(),
-                        },
-                    );
+    // Select the item that was selected when the app was last closed
+    if let Some(index) = app
+        .session
+        .selected_id
+        .take()
+        .and_then(|id| app.state.todo_list.index_of(id))
+        .filter(|index| app.visible.contains(index))
+    {
+        let id = app.state.todo_list[index].id;
+        app.state.selection = vec![id];
+        app.state.current = Some(id);
+    }
+    app.m_window.list_table.show_rows(table_rows(
+        &app.state.todo_list,
+        &app.visible,
+        &app.config.date_format(),
+    ));
+    view(&mut app.m_window, &app.state, &app.visible, true);
+}
+
+/// Turn a message of the window into an `update::Message` with its data,
+/// apply it to the state, and redraw the window from the new state.
+/// Messages only about the window, like dialogs and the theme, are handled here
+pub fn handle_message(app: &mut TodolistRS, message: Message) {
+    let m_window = &mut app.m_window;
+
+    let message = match message {
+        Message::List(message) => message,
+        Message::Create => {
+            // The description shows the selected item instead of a new one
+            if !m_window.create_button.active() {
+                return;
+            }
+            let description = m_window.description_input.value();
+            m_window.description_input.set_value("");
+            update::Message::Create {
+                description,
+                created_at: Utc::now(),
+            }
+        }
+        Message::Update => match app.state.current {
+            Some(id) => update::Message::SetCompleted(id, m_window.completed_input.value()),
+            None => return,
+        },
+        Message::Delete => {
+            let indices = app.state.selected_indices();
+            let question = match indices.as_slice() {
+                [] => return,
+                [index] => tr_args(
+                    "question-delete-one",
+                    &[(
+                        "description",
+                        &escape_label(&app.state.todo_list[*index].description),
+                    )],
+                ),
+                _ => tr_args("question-delete-many", &[("count", &indices.len())]),
+            };
+            if app.config.confirm_delete
+                && dialog::choice2_default(
+                    &question,
+                    &tr("button-cancel"),
+                    &tr("button-delete"),
+                    "",
+                ) != Some(1)
+            {
+                return;
+            }
+            update::Message::Delete(app.state.selection.clone())
+        }
+        Message::Select => {
+            let list_table = &mut m_window.list_table;
+            update::Message::Select {
+                selection: item_ids(
+                    &app.state.todo_list,
+                    &app.visible,
+                    &list_table.selected_rows(),
+                ),
+                current: list_table
+                    .current()
+                    .map(|row| app.state.todo_list[app.visible[row as usize]].id),
+            }
+        }
+        Message::ExportMarkdown => {
+            if let Some(path) =
+                choose_save_file(&tr("title-export-markdown"), "*.md", "todolist.md")
+            {
+                let checklist =
+                    markdown::to_checklist(app.visible.iter().map(|&i| &app.state.todo_list[i]));
+                write_text_file(&path, &checklist);
+            }
+            return;
+        }
+        Message::CopyMarkdown => {
+            app::copy(&markdown::to_checklist(
+                app.visible.iter().map(|&i| &app.state.todo_list[i]),
+            ));
+            return;
+        }
+        Message::ImportMarkdown => {
+            let Some(text) = choose_open_file(&tr("title-import-markdown"), "*.md")
+                .and_then(|path| read_text_file(&path))
+            else {
+                return;
+            };
+            update::Message::Insert(markdown::from_checklist(&text, Utc::now()))
+        }
+        Message::PasteMarkdown => {
+            let Some(text) = paste_text_dialog(&tr("title-paste-markdown")) else {
+                return;
+            };
+            update::Message::Insert(markdown::from_checklist(&text, Utc::now()))
+        }
+        Message::ExportOrg => {
+            if let Some(path) = choose_save_file(&tr("title-export-org"), "*.org", "todolist.org") {
+                write_text_file(
+                    &path,
+                    &orgmode::to_org(app.visible.iter().map(|&i| &app.state.todo_list[i])),
+                );
+            }
+            return;
+        }
+        Message::ImportOrg => {
+            let Some(text) = choose_open_file(&tr("title-import-org"), "*.org")
+                .and_then(|path| read_text_file(&path))
+            else {
+                return;
+            };
+            let nesting = match dialog::choice2_default(
+                &tr("question-org-nesting"),
+                &tr("button-cancel"),
+                &tr("button-nested"),
+                &tr("button-flattened"),
+            ) {
+                Some(1) => Nesting::Nested,
+                Some(2) => Nesting::Flattened,
+                _ => return,
+            };
+            update::Message::Insert(orgmode::from_org(&text, nesting, Utc::now()))
+        }
+        Message::ExportTaskwarrior => {
+            if let Some(path) =
+                choose_save_file(&tr("title-export-taskwarrior"), "*.json", "todolist.json")
+            {
+                let (json, report) =
+                    taskwarrior::to_json(app.visible.iter().map(|&i| &app.state.todo_list[i]));
+                write_text_file(&path, &json);
+                if let Some(summary) = report.summary() {
+                    dialog::message_default(&summary);
                 }
-                dump_list_items(&model);
-                description_input.set_value("");
-                app.s.send(Message::Filter);
-            }
-            Some(Message::Update) => {
-                let selected_name = list_browser.text(list_browser.value()).unwrap();
-                let datetime = selected_name.split("\t").collect::<Vec<&str>>()[1];
-                let index = model.iter().position(|s| s.datetime == datetime).unwrap();
-                let item = &mut model[index];
-                item.completed = completed_input.value();
-                dump_list_items(&model);
-                // description_input.set_value("");
-                app.s.send(Message::Filter);
-            }
-            Some(Message::Delete) => {
-                let selected_name = list_browser.text(list_browser.value()).unwrap();
-                let datetime = selected_name.split("\t").collect::<Vec<&str>>()[1];
-                // println!("{datetime}");
-                let index = model.iter().position(|s| s.datetime == datetime).unwrap();
-                model.remove(index);
-                dump_list_items(&model);
-                app.s.send(Message::Filter);
-                app.s.send(Message::Select)
-            }
-            Some(Message::Select) => {
-                if list_browser.value() == 0 || list_browser.value() == 1 {
-                    create_button.activate();
-                    description_input.set_value("");
-                    description_input.set_readonly(false);
-                    description_input.set_tooltip("");
-                    update_button.deactivate();
-                    delete_button.deactivate();
-                    completed_input.set_value(false);
-                    completed_input.deactivate();
-                } else {
-                    create_button.deactivate();
-                    completed_input.activate();
-                    let selected_name = list_browser.text(list_browser.value()).unwrap();
-                    let datetime = selected_name.split("\t").collect::<Vec<&str>>()[1];
-                    let index = model.iter().position(|s| s.datetime == datetime).unwrap();
-                    completed_input.set_value(model[index].completed);
-                    description_input.set_value(&model[index].description);
-                    description_input.set_readonly(true);
-                    description_input.set_tooltip(&model[index].description);
-                    update_button.activate();
-                    delete_button.activate();
+            }
+            return;
+        }
+        Message::ImportTaskwarrior => {
+            let Some(json) = choose_open_file(&tr("title-import-taskwarrior"), "*.json")
+                .and_then(|path| read_text_file(&path))
+            else {
+                return;
+            };
+            match taskwarrior::from_json(&json) {
+                Ok((items, report)) => {
+                    if let Some(summary) = report.summary() {
+                        dialog::message_default(&summary);
+                    }
+                    update::Message::Insert(items)
+                }
+                Err(err) => {
+                    eprintln!("{err:?}");
+                    dialog::alert_default(&tr_args("alert-not-task-export", &[("error", &err)]));
+                    return;
+                }
+            }
+        }
+        Message::ExportReport => {
+            let Some(grouping) = choose_option(
+                &tr("title-html-report"),
+                &tr("label-group-by"),
+                &GROUPINGS.map(tr),
+            ) else {
+                return;
+            };
+            if let Some(path) =
+                choose_save_file(&tr("title-export-report"), "*.html", "todolist.html")
+            {
+                let report = Report::new(
+                    app.visible.iter().map(|&i| &app.state.todo_list[i]),
+                    Grouping::from_index(grouping),
+                    app.config.date_format(),
+                );
+                write_text_file(&path, &report.to_html());
+            }
+            return;
+        }
+        Message::PrintReport => {
+            let Some(grouping) = choose_option(
+                &tr("title-print-report"),
+                &tr("label-group-by"),
+                &GROUPINGS.map(tr),
+            ) else {
+                return;
+            };
+            let report = Report::new(
+                app.visible.iter().map(|&i| &app.state.todo_list[i]),
+                Grouping::from_index(grouping),
+                app.config.date_format(),
+            );
+            if let Err(err) = report.print() {
+                eprintln!("{err:?}");
+            }
+            return;
+        }
+        Message::PasteBatch => {
+            let text = app.pasted.take();
+            let Some(descriptions) = batch_preview_dialog(&text) else {
+                return;
+            };
+            if descriptions.is_empty() {
+                return;
+            }
+            let created_at = Utc::now();
+            update::Message::Insert(
+                descriptions
+                    .into_iter()
+                    .map(|description| ListItem {
+                        completed: false,
+                        description,
+                        created_at,
+                        updated_at: created_at,
+                        ..Default::default()
+                    })
+                    .collect(),
+            )
+        }
+        Message::Preferences => {
+            let Some(mut config) = preferences_dialog(&app.config) else {
+                return;
+            };
+            let mut load = None;
+            if config.data_path != app.config.data_path {
+                // Open the list found there, or move the current one.
+                // The current list and lock stay if the file cannot be locked or loaded
+                let opened = lock_data_file(&config.data_path, &app.s).and_then(|instance| {
+                    match config.data_path.resolve().exists() {
+                        true => load_message(&config).map(|load| (instance, Some(load))),
+                        false => Some((instance, None)),
+                    }
+                });
+                match opened {
+                    Some((instance, opened)) => {
+                        app.instance = instance;
+                        // Pending changes belong to the previous file
+                        if app.unsaved {
+                            dump_list_items(&mut app.state.todo_list, &app.config.data_path);
+                            app.unsaved = false;
+                        }
+                        if opened.is_none() {
+                            dump_list_items(&mut app.state.todo_list, &config.data_path);
+                        }
+                        load = opened;
+                    }
+                    None => config.data_path = app.config.data_path.clone(),
+                }
+            }
+            if !app.config.autosave && config.autosave && app.unsaved {
+                dump_list_items(&mut app.state.todo_list, &config.data_path);
+                app.unsaved = false;
+            }
+            if config.font_size != app.config.font_size {
+                set_label_sizes(&app.wind, app.config.font_size, config.font_size);
+                m_window.description_input.set_text_size(config.font_size);
+                m_window.filter_input.set_text_size(config.font_size);
+                m_window.list_table.set_text_size(config.font_size);
+                m_window.menu_bar.set_text_size(config.font_size);
+                m_window.sort_choice.set_text_size(config.font_size);
+            }
+            if config.date_format() != app.config.date_format() {
+                m_window.list_table.update_rows(table_rows(
+                    &app.state.todo_list,
+                    &app.visible,
+                    &config.date_format(),
+                ));
+            }
+            app.palette = config.apply();
+            dump_config(&config, app.files.config.as_deref());
+            app.config = config;
+            show_theme(
+                &mut m_window.hint,
+                &mut m_window.menu_bar,
+                &app.config,
+                app.palette,
+            );
+            m_window.list_table.set_palette(app.palette);
+            match load {
+                Some(load) => load,
+                None => return,
+            }
+        }
+        Message::NewDataFile => {
+            let Some(path) = choose_save_file(&tr("title-new-data-file"), "*.dat", "todo.dat")
+            else {
+                return;
+            };
+            let path = path.to_string_lossy().into_owned();
+            // Starting it anew would throw away the open list
+            if path == app.config.data_path {
+                dialog::alert_default(&tr_args("alert-new-data-file-open", &[("path", &path)]));
+                return;
+            }
+            let Some(instance) = lock_data_file(&path, &app.s) else {
+                return;
+            };
+            if let Err(err) = TodoList::new_file(&path) {
+                eprintln!("{err:?}");
+                dialog::alert_default(&tr_args(
+                    "alert-write-file",
+                    &[("path", &path), ("error", &err)],
+                ));
+                return;
+            }
+            app.instance = instance;
+            if app.unsaved {
+                dump_list_items(&mut app.state.todo_list, &app.config.data_path);
+                app.unsaved = false;
+            }
+            app.config.data_path = path;
+            dump_config(&app.config, app.files.config.as_deref());
+            update::Message::Load {
+                items: vec![],
+                trash: vec![],
+            }
+        }
+        Message::OpenDataFile => {
+            let Some(path) = choose_open_file(&tr("title-open-data-file"), "*.dat") else {
+                return;
+            };
+            let config = Config {
+                data_path: path.to_string_lossy().into_owned(),
+                ..app.config.clone()
+            };
+            // The current list and lock stay if the file cannot be locked or loaded
+            let instance = match config.data_path != app.config.data_path {
+                true => match lock_data_file(&config.data_path, &app.s) {
+                    Some(instance) => Some(instance),
+                    None => return,
+                },
+                false => None,
+            };
+            if app.unsaved {
+                dump_list_items(&mut app.state.todo_list, &app.config.data_path);
+                app.unsaved = false;
+            }
+            let Some(load) = load_message(&config) else {
+                return;
+            };
+            if let Some(instance) = instance {
+                app.instance = instance;
+            }
+            app.config = config;
+            dump_config(&app.config, app.files.config.as_deref());
+            load
+        }
+        Message::Quit => {
+            app.a.quit();
+            return;
+        }
+        Message::NewTodo => {
+            m_window.description_input.take_focus().unwrap_or_default();
+            update::Message::Select {
+                selection: vec![],
+                current: None,
+            }
+        }
+        Message::ToggleCompleted => update::Message::ToggleCompleted(app.state.selection.clone()),
+        Message::Edit => {
+            let Some(index) = app.state.current_index() else {
+                return;
+            };
+            let Some(row) = row_of(&app.visible, index) else {
+                return;
+            };
+            m_window.list_table.make_visible(row);
+            let Some((x, y, w, h)) = m_window.list_table.cell_bounds(row, DESCRIPTION_COLUMN)
+            else {
+                return;
+            };
+            let Some(description) = inline_edit(
+                &mut app.wind,
+                x,
+                y,
+                w,
+                h,
+                &app.state.todo_list[index].description,
+            ) else {
+                return;
+            };
+            update::Message::Rename(app.state.todo_list[index].id, description)
+        }
+        Message::Duplicate => update::Message::Duplicate(app.state.selection.clone(), Utc::now()),
+        Message::CopyText => {
+            let descriptions = app
+                .state
+                .selected_indices()
+                .into_iter()
+                .map(|index| app.state.todo_list[index].description.as_str())
+                .collect::<Vec<&str>>();
+            if !descriptions.is_empty() {
+                app::copy(&descriptions.join("\n"));
+            }
+            return;
+        }
+        Message::History => {
+            let Some(index) = app.state.current_index() else {
+                return;
+            };
+            let item = &app.state.todo_list[index];
+            match app.state.todo_list.timeline(&app.config.data_path, item.id) {
+                Ok(events) => text_dialog(
+                    &tr_args(
+                        "title-history",
+                        &[("description", &display_text(&item.description))],
+                    ),
+                    &timeline_text(&events, &app.config.date_format()),
+                ),
+                Err(err) => {
+                    dialog::alert_default(&tr_args("alert-read-history", &[("error", &err)]))
                 }
             }
-            Some(Message::Filter) => {
-                let prefix = filter_input.value().to_lowercase();
-                list_browser.clear();
-                list_browser.add("@C221DESCRIPTION\t@C221DATETIME\t@C221COMPLETED");
-                for item in &model {
-                    if item.description.to_lowercase().starts_with(&prefix) {
-                        let content = format!(
-                            "{}\t{}\t{}",
-                            item.description,
-                            item.datetime,
-                            match item.completed {
-                                true => "✅",
-                                false => "❌",
-                            }
-                        );
-                        list_browser.add(&content);
+            return;
+        }
+        Message::Trash => {
+            let trash = app.state.todo_list.trash();
+            match trash_dialog(&trash_lines(trash, &app.config.date_format())) {
+                Some(TrashAction::Restore(indices)) => update::Message::Restore(
+                    indices
+                        .into_iter()
+                        .map(|index| trash[index].item.id)
+                        .collect(),
+                ),
+                Some(TrashAction::Empty)
+                    if dialog::choice2_default(
+                        &tr_args("question-empty-trash", &[("count", &trash.len())]),
+                        &tr("button-cancel"),
+                        &tr("button-empty-trash"),
+                        "",
+                    ) == Some(1) =>
+                {
+                    update::Message::EmptyTrash
+                }
+                _ => return,
+            }
+        }
+        Message::Archive => {
+            if !load_archive(&mut app.state.todo_list, &app.config.data_path) {
+                return;
+            }
+            update::Message::Archive(app.state.selection.clone())
+        }
+        Message::ShowArchive => {
+            if !load_archive(&mut app.state.todo_list, &app.config.data_path) {
+                return;
+            }
+            let archive = app.state.todo_list.archived();
+            let Some(indices) = archive_dialog(&archive_lines(archive, &app.config.date_format()))
+            else {
+                return;
+            };
+            update::Message::Unarchive(
+                indices
+                    .into_iter()
+                    .map(|index| archive[index].item.id)
+                    .collect(),
+            )
+        }
+        message @ (Message::ContextMenu | Message::ContextMenuAtPointer) => {
+            let Some(index) = app.state.current_index() else {
+                return;
+            };
+            let Some(row) = row_of(&app.visible, index) else {
+                return;
+            };
+            let list_table = &mut m_window.list_table;
+            let (x, y) = match message {
+                Message::ContextMenuAtPointer => app::event_coords(),
+                _ => {
+                    list_table.make_visible(row);
+                    match list_table.cell_bounds(row, DESCRIPTION_COLUMN) {
+                        Some((x, y, _, h)) => (x + WIDGET_PADDING * 2, y + h),
+                        None => (list_table.x(), list_table.y()),
                     }
                 }
-                app.s.send(Message::Select)
+            };
+            let completed = app.state.todo_list[index].completed;
+            let mut actions = vec![
+                (
+                    match completed {
+                        true => "action-mark-pending",
+                        false => "action-mark-done",
+                    },
+                    Message::ToggleCompleted,
+                ),
+                ("action-edit", Message::Edit),
+                ("action-duplicate", Message::Duplicate),
+                ("action-copy", Message::CopyText),
+                ("action-history", Message::History),
+            ];
+            if completed {
+                actions.push(("action-archive", Message::Archive));
+            }
+            actions.push(("action-delete", Message::Delete));
+            // Labelled with the message ids first, as the menu only takes static texts
+            let ids = actions.iter().map(|(id, _)| *id);
+            let menu = MenuItem::new(&ids.collect::<Vec<&str>>());
+            for (i, (id, _)) in actions.iter().enumerate() {
+                if let Some(mut item) = menu.at(i as i32) {
+                    item.set_label(&tr(id));
+                }
+            }
+            // The action at the position of the item picked
+            if let Some(picked) = menu.popup(x, y) {
+                let index =
+                    (0..actions.len()).find(|&i| menu.at(i as i32).as_ref() == Some(&picked));
+                if let Some((_, message)) = index.and_then(|i| actions.get(i)) {
+                    app.s.send(message.clone());
+                }
+            }
+            return;
+        }
+        Message::FocusFilter => {
+            let filter_input = &mut m_window.filter_input;
+            filter_input.take_focus().unwrap_or_default();
+            let end = filter_input.value().len() as i32;
+            filter_input.set_position(0).unwrap_or_default();
+            filter_input.set_mark(end).unwrap_or_default();
+            return;
+        }
+        Message::SortColumn(col) => update::Message::SetSortOrder(match col {
+            DESCRIPTION_COLUMN => SortOrder::Description,
+            // Newest first on the first click, then flipped on each one
+            DATETIME_COLUMN if app.state.sort_order == SortOrder::NewestFirst => {
+                SortOrder::OldestFirst
+            }
+            DATETIME_COLUMN => SortOrder::NewestFirst,
+            COMPLETED_COLUMN => SortOrder::PendingFirst,
+            _ => return,
+        }),
+        Message::Shortcuts => {
+            shortcuts_overlay(&app.wind, &app.keymap.descriptions());
+            return;
+        }
+        Message::About => {
+            dialog::message_default(&tr_args(
+                "about",
+                &[("version", &env!("CARGO_PKG_VERSION"))],
+            ));
+            return;
+        }
+        message @ (Message::Theme(_) | Message::Scheme(_)) => {
+            match message {
+                Message::Theme(name) => app.config.theme = name,
+                Message::Scheme(name) => app.config.scheme = name,
+                _ => return,
+            }
+            app.palette = app.config.apply();
+            dump_config(&app.config, app.files.config.as_deref());
+            show_theme(
+                &mut m_window.hint,
+                &mut m_window.menu_bar,
+                &app.config,
+                app.palette,
+            );
+            m_window.list_table.set_palette(app.palette);
+            return;
+        }
+        Message::Commands(commands) => {
+            let created_at = Utc::now();
+            update::Message::Insert(
+                commands
+                    .into_iter()
+                    .map(|command| match command {
+                        Command::Add(description) => ListItem {
+                            description,
+                            created_at,
+                            updated_at: created_at,
+                            ..Default::default()
+                        },
+                    })
+                    .collect(),
+            )
+        }
+        Message::Forwarded(args) => {
+            // Shown again, and raised, for the launch that asked for it
+            app.wind.show();
+            match parse_args(&args) {
+                Ok(commands) if !commands.is_empty() => app.s.send(Message::Commands(commands)),
+                Ok(_) => {}
+                Err(err) => eprintln!("{err}"),
             }
-            None => {}
+            return;
         }
+        Message::RefreshDates => {
+            m_window.list_table.update_rows(table_rows(
+                &app.state.todo_list,
+                &app.visible,
+                &app.config.date_format(),
+            ));
+            return;
+        }
+        Message::SystemTheme => {
+            if app.config.theme != SYSTEM_THEME {
+                return;
+            }
+            app.palette = app.config.apply();
+            show_theme(
+                &mut m_window.hint,
+                &mut m_window.menu_bar,
+                &app.config,
+                app.palette,
+            );
+            m_window.list_table.set_palette(app.palette);
+            return;
+        }
+    };
+
+    // The rows are rebuilt when the list, the filter or the sort order changed
+    let reorders = matches!(
+        message,
+        update::Message::Load { .. }
+            | update::Message::SetFilter(_)
+            | update::Message::SetSortOrder(_)
+    );
+    let arrows = matches!(
+        message,
+        update::Message::SelectPrevious | update::Message::SelectNext
+    );
+    let effects;
+    (app.state, effects) = update::update(mem::take(&mut app.state), message);
+    if effects.contains(&Effect::Save) {
+        save_list_items(&mut app.state.todo_list, &app.config, &mut app.unsaved);
+    }
+    if reorders || effects.contains(&Effect::Save) {
+        app.visible = app.state.visible();
+        m_window.list_table.show_rows(table_rows(
+            &app.state.todo_list,
+            &app.visible,
+            &app.config.date_format(),
+        ));
+    }
+    view(
+        m_window,
+        &app.state,
+        &app.visible,
+        effects.contains(&Effect::ScrollToCurrent),
+    );
+    if arrows {
+        m_window.list_table.take_focus().unwrap_or_default();
+    }
+}
+
+/// Create the message waiting loop
+/// and start the application
+pub fn message_waiting_loop(app: &mut TodolistRS) {
+    while app.a.wait() {
+        if let Some(message) = app.r.recv() {
+            handle_message(app, message);
+        }
+    }
+
+    if app.unsaved {
+        dump_list_items(&mut app.state.todo_list, &app.config.data_path);
+    }
+
+    app.session = Session {
+        window: Some([app.wind.x(), app.wind.y(), app.wind.w(), app.wind.h()]),
+        column_widths: app.m_window.list_table.column_widths(),
+        sort_order: app.state.sort_order,
+        filter: app.state.filter.clone(),
+        selected_id: app
+            .state
+            .current_index()
+            .map(|index| app.state.todo_list[index].id),
+    };
+    dump_session(
+        &app.session,
+        &app.config.data_path,
+        app.files.sessions.as_deref(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+
+    #[test]
+    fn labels_show_at_signs_literally() {
+        assert_eq!(escape_label("@C1 call Bob"), "@@C1 call Bob");
+        assert_eq!(escape_label("mail a@b.c @home"), "mail a@@b.c @@home");
+        assert_eq!(escape_label("no symbols"), "no symbols");
+    }
+
+    #[test]
+    fn timelines_show_one_line_per_event() {
+        let id = ItemId::new();
+        let event = |change| Event {
+            at: DateTime::default(),
+            id,
+            change,
+        };
+        let events = [
+            event(Change::Renamed {
+                description: "two\nlines".to_string(),
+            }),
+            event(Change::Completed),
+        ];
+        let text = timeline_text(&events, &DateFormat::Pattern(String::new()));
+        assert_eq!(text, "  Renamed to \"two lines\"\n  Marked as done");
     }
 }
@@ -0,0 +1,57 @@
+/// Number of changes that can be undone
+const HISTORY_LIMIT: usize = 100;
+
+/// Snapshots of the model taken before each change, to undo and redo them
+pub struct History<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self {
+            undo: vec![],
+            redo: vec![],
+        }
+    }
+}
+
+impl<T> History<T> {
+    /// Remember the model as it is before a change
+    pub fn record(&mut self, model: T) {
+        self.undo.push(model);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Change every snapshot, as when a part of the model is loaded later
+    pub fn for_each_mut(&mut self, f: impl FnMut(&mut T)) {
+        self.undo.iter_mut().chain(self.redo.iter_mut()).for_each(f);
+    }
+
+    /// Restore the model as it was before the last change.
+    /// Returns `false` if there is nothing to undo
+    pub fn undo(&mut self, model: &mut T) -> bool {
+        match self.undo.pop() {
+            Some(previous) => {
+                self.redo.push(std::mem::replace(model, previous));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Apply again the last undone change.
+    /// Returns `false` if there is nothing to redo
+    pub fn redo(&mut self, model: &mut T) -> bool {
+        match self.redo.pop() {
+            Some(next) => {
+                self.undo.push(std::mem::replace(model, next));
+                true
+            }
+            None => false,
+        }
+    }
+}
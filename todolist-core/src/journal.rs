@@ -0,0 +1,362 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, OpenOptions},
+    io::{self, Write},
+};
+
+use chrono::{DateTime, Utc};
+use resolve_path::PathResolveExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{ItemId, ListItem},
+    storage::StorageError,
+};
+
+/// Something that happened to an item, as recorded in the journal
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Event {
+    pub at: DateTime<Utc>,
+    pub id: ItemId,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Change {
+    /// The item was added at this index of the list
+    Created {
+        item: ListItem,
+        position: usize,
+    },
+    Renamed {
+        description: String,
+    },
+    Completed,
+    Uncompleted,
+    Deleted,
+    /// A deleted item came back at this index of the list, by undo or redo
+    Restored {
+        item: ListItem,
+        position: usize,
+    },
+    /// The item was replaced by this one, as by undo or redo
+    Edited {
+        item: ListItem,
+    },
+    /// The item moved to the archive
+    Archived,
+    /// An archived item came back at this index of the list
+    Unarchived {
+        item: ListItem,
+        position: usize,
+    },
+    /// The item was dropped from the trash for good
+    Purged,
+}
+
+/// Journal of a data file: the file with `.journal` appended to its name.
+/// One event per line, as JSON, only ever appended to
+pub fn journal_path(data_path: &str) -> String {
+    format!("{data_path}.journal")
+}
+
+/// Read the events of a journal, oldest first. A missing journal holds no events.
+/// A last line cut short, by a crash while it was written, is left out
+pub fn read_events(path: &str) -> Result<Vec<Event>, StorageError> {
+    let text = match fs::read_to_string(path.resolve()) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+    let Some((complete, _)) = text.rsplit_once('\n') else {
+        return Ok(vec![]);
+    };
+
+    complete
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+/// Whether a journal is missing or empty, without reading it
+pub fn is_empty(path: &str) -> bool {
+    fs::metadata(path.resolve()).map_or(true, |metadata| metadata.len() == 0)
+}
+
+/// Add the events at the end of a journal, creating it if needed
+pub fn append_events(path: &str, events: &[Event]) -> Result<(), StorageError> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&serde_json::to_string(event)?);
+        lines.push('\n');
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path.resolve())?;
+    file.write_all(lines.as_bytes())?;
+    Ok(())
+}
+
+/// The list the events lead to, starting from an empty one.
+/// Renaming and completing set the timestamps of the item to the time of the event
+pub fn replay(events: &[Event]) -> Vec<ListItem> {
+    let mut items: Vec<ListItem> = vec![];
+    for event in events {
+        let position = items.iter().position(|item| item.id == event.id);
+        match (&event.change, position) {
+            (
+                Change::Created { item, position }
+                | Change::Restored { item, position }
+                | Change::Unarchived { item, position },
+                None,
+            ) => items.insert((*position).min(items.len()), item.clone()),
+            (Change::Renamed { description }, Some(index)) => {
+                items[index].description = description.clone();
+                items[index].updated_at = event.at;
+            }
+            (Change::Completed | Change::Uncompleted, Some(index)) => {
+                let item = &mut items[index];
+                item.completed = event.change == Change::Completed;
+                item.completed_at = item.completed.then_some(event.at);
+                item.updated_at = event.at;
+            }
+            (Change::Deleted | Change::Archived, Some(index)) => {
+                items.remove(index);
+            }
+            (Change::Edited { item }, Some(index)) => items[index] = item.clone(),
+            // Events about items that are not there, or already are, change nothing
+            _ => {}
+        }
+    }
+    items
+}
+
+/// When each completed item of the events was marked as done.
+/// Items added already completed count from then, and unarchived ones from their return
+pub fn completion_times(events: &[Event]) -> HashMap<ItemId, DateTime<Utc>> {
+    let mut times = HashMap::new();
+    for event in events {
+        match &event.change {
+            Change::Completed => {
+                times.insert(event.id, event.at);
+            }
+            Change::Uncompleted => {
+                times.remove(&event.id);
+            }
+            Change::Unarchived { item, .. } if item.completed => {
+                times.insert(event.id, event.at);
+            }
+            Change::Created { item, .. } | Change::Restored { item, .. } if item.completed => {
+                times.entry(event.id).or_insert(event.at);
+            }
+            _ => {}
+        }
+    }
+    times
+}
+
+/// Changes turning the `before` list into the `after` one, items being matched by id.
+/// Deletions come first and additions last, by increasing index,
+/// so that replaying them in order gives the `after` list
+pub fn changes(before: &[ListItem], after: &[ListItem]) -> Vec<(ItemId, Change)> {
+    let previous = before
+        .iter()
+        .map(|item| (item.id, item))
+        .collect::<HashMap<ItemId, &ListItem>>();
+    let next = after
+        .iter()
+        .map(|item| item.id)
+        .collect::<HashSet<ItemId>>();
+
+    let mut changes = before
+        .iter()
+        .filter(|item| !next.contains(&item.id))
+        .map(|item| (item.id, Change::Deleted))
+        .collect::<Vec<(ItemId, Change)>>();
+
+    // Whole items, as their timestamps go back and forth with them
+    for item in after {
+        if previous.get(&item.id).is_some_and(|old| *old != item) {
+            changes.push((item.id, Change::Edited { item: item.clone() }));
+        }
+    }
+
+    for (position, item) in after.iter().enumerate() {
+        if !previous.contains_key(&item.id) {
+            let item = item.clone();
+            changes.push((item.id, Change::Restored { item, position }));
+        }
+    }
+
+    changes
+}
+
+/// Change of an item getting this completed state
+pub fn completion(completed: bool) -> Change {
+    match completed {
+        true => Change::Completed,
+        false => Change::Uncompleted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        env::temp_dir()
+            .join(format!("todolist-core-journal-{}-{name}", process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn item(description: &str) -> ListItem {
+        ListItem {
+            description: description.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn event(id: ItemId, change: Change) -> Event {
+        Event {
+            at: Utc::now(),
+            id,
+            change,
+        }
+    }
+
+    #[test]
+    fn appended_events_are_read_back_in_order() {
+        let path = temp_path("append.journal");
+        let milk = item("buy @milk\tnow");
+        let first = vec![event(
+            milk.id,
+            Change::Created {
+                item: milk.clone(),
+                position: 0,
+            },
+        )];
+        let second = vec![
+            event(milk.id, Change::Completed),
+            event(milk.id, Change::Deleted),
+        ];
+        append_events(&path, &first).unwrap();
+        append_events(&path, &second).unwrap();
+        assert_eq!(read_events(&path).unwrap(), [first, second].concat());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_journal_holds_no_events() {
+        assert!(read_events(&temp_path("missing.journal"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn line_cut_short_is_left_out() {
+        let path = temp_path("cut.journal");
+        let milk = item("milk");
+        append_events(&path, &[event(milk.id, Change::Completed)]).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"at\":\"2024-01-01T00:").unwrap();
+        assert_eq!(read_events(&path).unwrap().len(), 1);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_lines_are_rejected() {
+        let path = temp_path("corrupt.journal");
+        fs::write(&path, "not an event\n").unwrap();
+        assert!(matches!(read_events(&path), Err(StorageError::Journal(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn completion_times_follow_the_last_change() {
+        let (milk, bread) = (item("milk"), item("bread"));
+        let at = |seconds| DateTime::from_timestamp(seconds, 0).unwrap();
+        let change = |seconds, id, change| Event {
+            at: at(seconds),
+            id,
+            change,
+        };
+        let unarchived = Change::Unarchived {
+            item: ListItem {
+                completed: true,
+                ..bread.clone()
+            },
+            position: 0,
+        };
+        let events = [
+            change(1, milk.id, Change::Completed),
+            change(2, bread.id, Change::Completed),
+            change(3, milk.id, Change::Uncompleted),
+            change(4, bread.id, Change::Archived),
+            change(5, bread.id, unarchived),
+        ];
+        let times = completion_times(&events);
+        assert_eq!(times.get(&bread.id), Some(&at(5)));
+        assert!(!times.contains_key(&milk.id));
+    }
+
+    #[test]
+    fn changes_replay_into_the_new_list() {
+        let (a, c) = (item("a"), item("c"));
+        let before = vec![a.clone(), item("b"), c.clone()];
+        let after = vec![
+            item("new"),
+            ListItem {
+                description: "A".to_string(),
+                completed: true,
+                depth: 1,
+                ..a.clone()
+            },
+            c.clone(),
+        ];
+        // From nothing to `before`, then to `after`
+        let events = changes(&[], &before)
+            .into_iter()
+            .chain(changes(&before, &after))
+            .map(|(id, change)| event(id, change))
+            .collect::<Vec<Event>>();
+        assert_eq!(replay(&events), after);
+
+        let kinds = changes(&before, &after)
+            .into_iter()
+            .filter(|(id, _)| *id == a.id)
+            .map(|(_, change)| change)
+            .collect::<Vec<Change>>();
+        assert!(matches!(kinds[..], [Change::Edited { .. }]));
+    }
+
+    #[test]
+    fn completing_sets_the_timestamps_of_the_event() {
+        let milk = item("milk");
+        let at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let created = Change::Created {
+            item: milk.clone(),
+            position: 0,
+        };
+        let events = [
+            event(milk.id, created),
+            Event {
+                at,
+                id: milk.id,
+                change: Change::Completed,
+            },
+        ];
+        let replayed = &replay(&events)[0];
+        assert_eq!(replayed.completed_at, Some(at));
+        assert_eq!(replayed.updated_at, at);
+    }
+}
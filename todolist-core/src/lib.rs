@@ -0,0 +1,16 @@
+//! To-do list model of the app, free of any UI: the items, their trash and archive,
+//! the operations on them with undo and redo, filtering and sorting,
+//! their storage with a journal of every change,
+//! and the state of the main window with the messages that change it
+
+mod history;
+mod journal;
+mod models;
+mod storage;
+mod todo_list;
+pub mod update;
+
+pub use journal::{Change, Event};
+pub use models::{ArchivedItem, ItemId, ListItem, SortOrder, TrashedItem};
+pub use storage::StorageError;
+pub use todo_list::TodoList;
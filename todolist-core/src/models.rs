@@ -0,0 +1,118 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
+
+/// Identifier of an item, kept across edits, undo and saves
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ItemId(Uuid);
+
+impl ItemId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+/// A new identifier, so items created with `..Default::default()` are told apart
+impl Default for ItemId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ListItem {
+    pub completed: bool,
+    pub description: String,
+    /// Written as RFC 3339. Data files of earlier versions hold a `datetime`
+    /// formatted for display instead, read back as local time
+    #[serde(alias = "datetime", deserialize_with = "deserialize_created_at")]
+    pub created_at: DateTime<Utc>,
+    /// Priority letter, `A` being the highest
+    #[serde(default)]
+    pub priority: Option<char>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Date (and optionally time) formatted with `DATE_FORMAT` or `DATE_TIME_FORMAT`
+    #[serde(default)]
+    pub scheduled: Option<String>,
+    /// Date (and optionally time) formatted with `DATE_FORMAT` or `DATE_TIME_FORMAT`
+    #[serde(default)]
+    pub deadline: Option<String>,
+    #[serde(default)]
+    pub properties: Vec<(String, String)>,
+    /// Outline depth, 0 for top-level items
+    #[serde(default)]
+    pub depth: usize,
+    /// Given on load to items saved before there were identifiers
+    #[serde(default)]
+    pub id: ItemId,
+    /// Last time the description or the completed state changed
+    #[serde(default)]
+    pub updated_at: DateTime<Utc>,
+    /// Set exactly when the item is completed
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Formats of the creation dates written by earlier versions,
+/// the default display format of the app first
+const LEGACY_FORMATS: [&str; 3] = ["%d-%m-%Y • %H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"];
+
+/// A creation date written as RFC 3339, or by an earlier version in local time
+pub fn parse_created_at(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+        return Some(at.to_utc());
+    }
+    let naive = LEGACY_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+            date.and_hms_opt(0, 0, 0)
+        })?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|at| at.to_utc())
+}
+
+/// Creation dates that cannot be read become the Unix epoch, for the loader to replace
+fn deserialize_created_at<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    Ok(parse_created_at(&text).unwrap_or_default())
+}
+
+/// A deleted item, kept in the trash until it is restored or purged
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TrashedItem {
+    pub item: ListItem,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// A completed item moved out of the list, kept in the archive until it is unarchived
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ArchivedItem {
+    pub item: ListItem,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Order of the rows in the list
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum SortOrder {
+    #[default]
+    NewestFirst,
+    OldestFirst,
+    Description,
+    PendingFirst,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 4] = [
+        SortOrder::NewestFirst,
+        SortOrder::OldestFirst,
+        SortOrder::Description,
+        SortOrder::PendingFirst,
+    ];
+}
@@ -0,0 +1,165 @@
+use std::{error::Error, fmt, fs, io};
+
+use resolve_path::PathResolveExt;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Failure to read or write a data file
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    /// The file is not a list saved by the app
+    Decode(rmp_serde::decode::Error),
+    Encode(rmp_serde::encode::Error),
+    /// A line of the journal is not an event
+    Journal(serde_json::Error),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Io(err) => write!(f, "{err}"),
+            StorageError::Decode(err) => write!(f, "not a to-do list: {err}"),
+            StorageError::Encode(err) => write!(f, "could not encode the list: {err}"),
+            StorageError::Journal(err) => write!(f, "corrupt journal: {err}"),
+        }
+    }
+}
+
+impl Error for StorageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StorageError::Io(err) => Some(err),
+            StorageError::Decode(err) => Some(err),
+            StorageError::Encode(err) => Some(err),
+            StorageError::Journal(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(err: io::Error) -> Self {
+        StorageError::Io(err)
+    }
+}
+
+impl From<rmp_serde::decode::Error> for StorageError {
+    fn from(err: rmp_serde::decode::Error) -> Self {
+        StorageError::Decode(err)
+    }
+}
+
+impl From<rmp_serde::encode::Error> for StorageError {
+    fn from(err: rmp_serde::encode::Error) -> Self {
+        StorageError::Encode(err)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(err: serde_json::Error) -> Self {
+        StorageError::Journal(err)
+    }
+}
+
+/// Trash of a data file: the file with `.trash` appended to its name
+pub fn trash_path(data_path: &str) -> String {
+    format!("{data_path}.trash")
+}
+
+/// Archive of a data file: the file with `.archive` appended to its name
+pub fn archive_path(data_path: &str) -> String {
+    format!("{data_path}.archive")
+}
+
+/// Read the items of a data file, a trash or an archive. A missing or empty file holds no items
+pub fn read_items<T: DeserializeOwned>(path: &str) -> Result<Vec<T>, StorageError> {
+    let data = match fs::read(path.resolve()) {
+        Ok(data) => data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+    if data.is_empty() {
+        return Ok(vec![]);
+    }
+
+    Ok(rmp_serde::from_slice(&data)?)
+}
+
+/// Write the items to a data file, a trash or an archive, replacing it
+pub fn write_items<T: Serialize>(path: &str, items: &[T]) -> Result<(), StorageError> {
+    fs::write(path.resolve(), rmp_serde::to_vec(items)?)?;
+    Ok(())
+}
+
+/// Delete a file, if there is one
+pub fn remove_file(path: &str) -> Result<(), StorageError> {
+    match fs::remove_file(path.resolve()) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use chrono::DateTime;
+
+    use super::*;
+    use crate::models::ListItem;
+
+    /// Path of a file in the temporary directory, unique to the test
+    fn temp_path(name: &str) -> String {
+        env::temp_dir()
+            .join(format!("todolist-core-{}-{name}", process::id()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn written_items_are_read_back() {
+        let path = temp_path("round-trip.dat");
+        let items = vec![
+            ListItem {
+                description: "buy milk".to_string(),
+                created_at: DateTime::from_timestamp(1_704_103_200, 0).unwrap(),
+                ..Default::default()
+            },
+            ListItem {
+                completed: true,
+                description: "@C1 call Bob\twith tabs".to_string(),
+                tags: vec!["work".to_string()],
+                depth: 2,
+                ..Default::default()
+            },
+        ];
+        write_items(&path, &items).unwrap();
+        assert_eq!(read_items::<ListItem>(&path).unwrap(), items);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_file_holds_no_items() {
+        assert!(read_items::<ListItem>(&temp_path("missing.dat"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn empty_file_holds_no_items() {
+        let path = temp_path("empty.dat");
+        fs::write(&path, []).unwrap();
+        assert!(read_items::<ListItem>(&path).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn other_files_are_rejected() {
+        let path = temp_path("other.dat");
+        fs::write(&path, "- [ ] not a data file").unwrap();
+        assert!(matches!(
+            read_items::<ListItem>(&path),
+            Err(StorageError::Decode(_))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,1043 @@
+use std::{collections::HashSet, fs, mem, ops::Index};
+
+use chrono::{DateTime, Utc};
+use resolve_path::PathResolveExt;
+
+use crate::{
+    history::History,
+    journal::{
+        self, append_events, completion_times, journal_path, read_events, replay, Change, Event,
+    },
+    models::{ArchivedItem, ItemId, ListItem, SortOrder, TrashedItem},
+    storage::{archive_path, read_items, remove_file, trash_path, write_items, StorageError},
+};
+
+/// The items, the trash and the archive, as saved before each change to undo it
+type Snapshot = (Vec<ListItem>, Vec<TrashedItem>, Option<Vec<ArchivedItem>>);
+
+/// The to-do items, newest first, with the trash of deleted ones,
+/// the archive of completed ones and the history of their changes.
+/// Items are addressed by their index in the list, and every change can be undone.
+/// Indices out of the list panic, as with a slice
+#[derive(Default)]
+pub struct TodoList {
+    items: Vec<ListItem>,
+    /// Deleted items, last deleted first
+    trash: Vec<TrashedItem>,
+    /// Archived items, last archived first. `None` until read with `load_archive`
+    archive: Option<Vec<ArchivedItem>>,
+    history: History<Snapshot>,
+    /// Changes not yet written to the journal
+    events: Vec<Event>,
+}
+
+impl TodoList {
+    pub fn new(items: Vec<ListItem>) -> Self {
+        Self::with_trash(items, vec![])
+    }
+
+    pub fn with_trash(items: Vec<ListItem>, trash: Vec<TrashedItem>) -> Self {
+        Self {
+            items,
+            trash,
+            archive: None,
+            history: History::default(),
+            events: vec![],
+        }
+    }
+
+    /// Read the list and its trash from a data file, with no history.
+    /// The archive is left on disk
+    pub fn load(path: &str) -> Result<Self, StorageError> {
+        let mut items = read_items(path)?;
+        migrate_timestamps(&mut items, path);
+        Ok(Self::with_trash(items, read_items(&trash_path(path))?))
+    }
+
+    /// Read the archive of the data file, if it is not read yet
+    pub fn load_archive(&mut self, path: &str) -> Result<(), StorageError> {
+        if self.archive.is_some() {
+            return Ok(());
+        }
+        let archive = read_items(&archive_path(path))?;
+        // It was the same before, as it only changes once read
+        self.history
+            .for_each_mut(|(_, _, snapshot)| *snapshot = Some(archive.clone()));
+        self.archive = Some(archive);
+        Ok(())
+    }
+
+    /// Read the list back from the journal of a data file, with no history,
+    /// as when the data file itself is lost or damaged
+    pub fn rebuild(path: &str) -> Result<Self, StorageError> {
+        let events = read_events(&journal_path(path))?;
+        let mut items = replay(&events);
+        migrate_timestamps(&mut items, path);
+        Ok(Self::new(items))
+    }
+
+    /// Start an empty list in a new data file, deleting the journal, trash and archive
+    /// of any file it replaces, which would otherwise be read back with it
+    pub fn new_file(path: &str) -> Result<Self, StorageError> {
+        for companion in [journal_path(path), trash_path(path), archive_path(path)] {
+            remove_file(&companion)?;
+        }
+        let mut todo_list = Self::default();
+        todo_list.save(path)?;
+        Ok(todo_list)
+    }
+
+    /// Write the list to a data file, its trash and its archive, if it was read, next to it,
+    /// and its changes since the last save to the journal.
+    /// A missing or empty journal starts with the creation of the items as they are now.
+    /// The list is written even if the journal cannot be, whose changes are kept for the next save
+    pub fn save(&mut self, path: &str) -> Result<(), StorageError> {
+        let journal = journal_path(path);
+        if journal::is_empty(&journal) {
+            self.events = self
+                .items
+                .iter()
+                .enumerate()
+                .map(|(position, item)| Event {
+                    at: Utc::now(),
+                    id: item.id,
+                    change: Change::Created {
+                        item: item.clone(),
+                        position,
+                    },
+                })
+                .collect();
+        }
+        let journaled = append_events(&journal, &self.events);
+        if journaled.is_ok() {
+            self.events.clear();
+        }
+        match self.trash.is_empty() {
+            true => remove_file(&trash_path(path))?,
+            false => write_items(&trash_path(path), &self.trash)?,
+        }
+        match self.archive.as_deref() {
+            None => {}
+            Some([]) => remove_file(&archive_path(path))?,
+            Some(archive) => write_items(&archive_path(path), archive)?,
+        }
+        write_items(path, &self.items)?;
+        journaled
+    }
+
+    /// Indices of the completed items marked as done before the date
+    pub fn completed_before(&self, before: DateTime<Utc>) -> Vec<usize> {
+        (0..self.items.len())
+            .filter(|&index| self.items[index].completed_at.is_some_and(|at| at < before))
+            .collect()
+    }
+
+    /// Events of the item, oldest first, from the journal of the data file
+    /// and the changes not yet saved
+    pub fn timeline(&self, path: &str, id: ItemId) -> Result<Vec<Event>, StorageError> {
+        let mut events = read_events(&journal_path(path))?;
+        events.extend(self.events.iter().cloned());
+        events.retain(|event| event.id == id);
+        Ok(events)
+    }
+
+    pub fn items(&self) -> &[ListItem] {
+        &self.items
+    }
+
+    pub fn into_items(self) -> Vec<ListItem> {
+        self.items
+    }
+
+    pub fn trash(&self) -> &[TrashedItem] {
+        &self.trash
+    }
+
+    /// Archived items, or none if the archive is not read
+    pub fn archived(&self) -> &[ArchivedItem] {
+        self.archive.as_deref().unwrap_or_default()
+    }
+
+    pub fn into_parts(self) -> (Vec<ListItem>, Vec<TrashedItem>) {
+        (self.items, self.trash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Add a pending item at the top.
+    /// Returns `false`, changing nothing, if the description is blank
+    pub fn create(&mut self, description: &str, created_at: DateTime<Utc>) -> bool {
+        if description.trim().is_empty() {
+            return false;
+        }
+        self.insert(vec![ListItem {
+            completed: false,
+            description: description.to_string(),
+            created_at,
+            updated_at: created_at,
+            ..Default::default()
+        }]);
+        true
+    }
+
+    /// Add items at the top, in their order. Returns how many were added
+    pub fn insert(&mut self, items: Vec<ListItem>) -> usize {
+        if items.is_empty() {
+            return 0;
+        }
+        self.record();
+        let count = items.len();
+        self.items.splice(0..0, items);
+        for position in 0..count {
+            let item = self.items[position].clone();
+            self.log(Utc::now(), item.id, Change::Created { item, position });
+        }
+        count
+    }
+
+    /// Mark an item as completed or pending.
+    /// Returns `false`, changing nothing, if it already was
+    pub fn set_completed(&mut self, index: usize, completed: bool) -> bool {
+        if self.items[index].completed == completed {
+            return false;
+        }
+        self.record();
+        let now = Utc::now();
+        complete(&mut self.items[index], completed, now);
+        self.log(now, self.items[index].id, journal::completion(completed));
+        true
+    }
+
+    /// Replace the description of an item.
+    /// Returns `false`, changing nothing, if the new one is blank or the same
+    pub fn set_description(&mut self, index: usize, description: &str) -> bool {
+        if description.trim().is_empty() || self.items[index].description == description {
+            return false;
+        }
+        self.record();
+        let now = Utc::now();
+        let item = &mut self.items[index];
+        item.description = description.to_string();
+        item.updated_at = now;
+        let description = description.to_string();
+        self.log(now, self.items[index].id, Change::Renamed { description });
+        true
+    }
+
+    /// Flip the completed state of the items, as one change.
+    /// Returns `false` if there are none
+    pub fn toggle_completed(&mut self, indices: &[usize]) -> bool {
+        if indices.is_empty() {
+            return false;
+        }
+        self.record();
+        let now = Utc::now();
+        for index in unique(indices) {
+            let item = &mut self.items[index];
+            complete(item, !item.completed, now);
+            let (id, completed) = (item.id, item.completed);
+            self.log(now, id, journal::completion(completed));
+        }
+        true
+    }
+
+    /// Add copies of the items, created at `created_at` and with new identifiers,
+    /// at the top in the same order. Returns how many were added
+    pub fn duplicate(&mut self, indices: &[usize], created_at: DateTime<Utc>) -> usize {
+        let copies = indices
+            .iter()
+            .map(|&index| ListItem {
+                created_at,
+                id: ItemId::new(),
+                updated_at: created_at,
+                completed_at: self.items[index].completed.then_some(created_at),
+                ..self.items[index].clone()
+            })
+            .collect();
+        self.insert(copies)
+    }
+
+    /// Move the items to the trash, as one change. Returns how many were moved
+    pub fn delete(&mut self, indices: &[usize]) -> usize {
+        if indices.is_empty() {
+            return 0;
+        }
+        self.record();
+        let indices = unique(indices);
+        let deleted_at = Utc::now();
+        let mut trashed = Vec::with_capacity(indices.len());
+        // From the end, so the remaining indices stay valid
+        for &index in indices.iter().rev() {
+            let item = self.items.remove(index);
+            self.log(deleted_at, item.id, Change::Deleted);
+            trashed.push(TrashedItem { item, deleted_at });
+        }
+        trashed.reverse();
+        self.trash.splice(0..0, trashed);
+        indices.len()
+    }
+
+    /// Put the trashed items with these identifiers back at the top, in the order
+    /// of the trash, as one change. Returns how many were restored
+    pub fn restore(&mut self, ids: &[ItemId]) -> usize {
+        let ids = ids.iter().copied().collect::<HashSet<ItemId>>();
+        if !self
+            .trash
+            .iter()
+            .any(|trashed| ids.contains(&trashed.item.id))
+        {
+            return 0;
+        }
+        self.record();
+        let (restored, kept) = mem::take(&mut self.trash)
+            .into_iter()
+            .partition::<Vec<TrashedItem>, _>(|trashed| ids.contains(&trashed.item.id));
+        self.trash = kept;
+        let count = restored.len();
+        self.items
+            .splice(0..0, restored.into_iter().map(|trashed| trashed.item));
+        for position in 0..count {
+            let item = self.items[position].clone();
+            self.log(Utc::now(), item.id, Change::Restored { item, position });
+        }
+        count
+    }
+
+    /// Move the completed items among these to the archive, as one change.
+    /// Returns how many were moved, none if the archive is not read
+    pub fn archive(&mut self, indices: &[usize]) -> usize {
+        let indices = unique(indices)
+            .into_iter()
+            .filter(|&index| self.items[index].completed)
+            .collect::<Vec<usize>>();
+        if indices.is_empty() || self.archive.is_none() {
+            return 0;
+        }
+        self.record();
+        let archived_at = Utc::now();
+        let mut archived = Vec::with_capacity(indices.len());
+        // From the end, so the remaining indices stay valid
+        for &index in indices.iter().rev() {
+            let item = self.items.remove(index);
+            self.log(archived_at, item.id, Change::Archived);
+            archived.push(ArchivedItem { item, archived_at });
+        }
+        archived.reverse();
+        if let Some(archive) = &mut self.archive {
+            archive.splice(0..0, archived);
+        }
+        indices.len()
+    }
+
+    /// Put the archived items with these identifiers back at the top, in the order
+    /// of the archive, as one change. Returns how many were put back
+    pub fn unarchive(&mut self, ids: &[ItemId]) -> usize {
+        let ids = ids.iter().copied().collect::<HashSet<ItemId>>();
+        if !self
+            .archived()
+            .iter()
+            .any(|archived| ids.contains(&archived.item.id))
+        {
+            return 0;
+        }
+        self.record();
+        let (unarchived, kept) = self
+            .archive
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .partition::<Vec<ArchivedItem>, _>(|archived| ids.contains(&archived.item.id));
+        self.archive = Some(kept);
+        let count = unarchived.len();
+        self.items
+            .splice(0..0, unarchived.into_iter().map(|archived| archived.item));
+        for position in 0..count {
+            let item = self.items[position].clone();
+            self.log(Utc::now(), item.id, Change::Unarchived { item, position });
+        }
+        count
+    }
+
+    /// Drop every item of the trash for good, with no undo. Returns how many were dropped
+    pub fn empty_trash(&mut self) -> usize {
+        self.purge_trash(DateTime::<Utc>::MAX_UTC)
+    }
+
+    /// Drop the items deleted before the date for good, with no undo, as when the list is loaded.
+    /// Returns how many were dropped
+    pub fn purge_trash(&mut self, deleted_before: DateTime<Utc>) -> usize {
+        let (purged, kept) = mem::take(&mut self.trash)
+            .into_iter()
+            .partition::<Vec<TrashedItem>, _>(|trashed| trashed.deleted_at < deleted_before);
+        self.trash = kept;
+        if purged.is_empty() {
+            return 0;
+        }
+
+        // Nor can undo bring them back
+        let ids = purged
+            .iter()
+            .map(|trashed| trashed.item.id)
+            .collect::<HashSet<ItemId>>();
+        self.history.for_each_mut(|(items, trash, _)| {
+            items.retain(|item| !ids.contains(&item.id));
+            trash.retain(|trashed| !ids.contains(&trashed.item.id));
+        });
+        let now = Utc::now();
+        for trashed in &purged {
+            self.log(now, trashed.item.id, Change::Purged);
+        }
+        purged.len()
+    }
+
+    /// Indices of the items whose description starts with the prefix, ignoring case,
+    /// in the order they are shown
+    pub fn filter(&self, prefix: &str, sort_order: SortOrder) -> Vec<usize> {
+        let prefix = prefix.to_lowercase();
+        let mut visible = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.description.to_lowercase().starts_with(&prefix))
+            .map(|(index, _)| index)
+            .collect::<Vec<usize>>();
+
+        // New items are inserted at the top of the list
+        match sort_order {
+            SortOrder::NewestFirst => {}
+            SortOrder::OldestFirst => visible.reverse(),
+            SortOrder::Description => {
+                visible.sort_by_cached_key(|&index| self.items[index].description.to_lowercase())
+            }
+            SortOrder::PendingFirst => visible.sort_by_key(|&index| self.items[index].completed),
+        }
+
+        visible
+    }
+
+    /// Index of the first item with this description and creation date
+    pub fn find(&self, description: &str, created_at: DateTime<Utc>) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|item| item.description == description && item.created_at == created_at)
+    }
+
+    /// Index of the item with this identifier
+    pub fn index_of(&self, id: ItemId) -> Option<usize> {
+        self.items.iter().position(|item| item.id == id)
+    }
+
+    /// Restore the list as it was before the last change.
+    /// Returns `false` if there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        self.travel(History::undo)
+    }
+
+    /// Apply again the last undone change.
+    /// Returns `false` if there is nothing to redo
+    pub fn redo(&mut self) -> bool {
+        self.travel(History::redo)
+    }
+
+    /// Remember the list, the trash and the archive as they are before a change
+    fn record(&mut self) {
+        let snapshot = (self.items.clone(), self.trash.clone(), self.archive.clone());
+        self.history.record(snapshot);
+    }
+
+    /// Undo or redo, logging what changed in the list
+    fn travel(&mut self, step: fn(&mut History<Snapshot>, &mut Snapshot) -> bool) -> bool {
+        let before = self.items.clone();
+        let mut snapshot = (
+            mem::take(&mut self.items),
+            mem::take(&mut self.trash),
+            self.archive.take(),
+        );
+        let moved = step(&mut self.history, &mut snapshot);
+        (self.items, self.trash, self.archive) = snapshot;
+        self.log_changes(&before);
+        moved
+    }
+
+    /// Log a change made at `at`, which is also the time it sets on the item, if any
+    fn log(&mut self, at: DateTime<Utc>, id: ItemId, change: Change) {
+        self.events.push(Event { at, id, change });
+    }
+
+    /// Log what turned the `before` list into the current one
+    fn log_changes(&mut self, before: &[ListItem]) {
+        let now = Utc::now();
+        for (id, change) in journal::changes(before, &self.items) {
+            self.log(now, id, change);
+        }
+    }
+}
+
+impl Index<usize> for TodoList {
+    type Output = ListItem;
+
+    fn index(&self, index: usize) -> &ListItem {
+        &self.items[index]
+    }
+}
+
+/// Mark the item as completed or pending at `at`
+fn complete(item: &mut ListItem, completed: bool, at: DateTime<Utc>) {
+    item.completed = completed;
+    item.completed_at = completed.then_some(at);
+    item.updated_at = at;
+}
+
+/// Fill in the timestamps of items saved by earlier versions: creation dates
+/// that cannot be read become the last write of the data file, missing update dates
+/// the creation date, and completed items count as done when the journal last saw
+/// them done, or else when last changed. A journal that cannot be read counts as empty
+fn migrate_timestamps(items: &mut [ListItem], path: &str) {
+    if items
+        .iter()
+        .any(|item| item.created_at == DateTime::UNIX_EPOCH)
+    {
+        let modified = fs::metadata(path.resolve()).and_then(|metadata| metadata.modified());
+        let written = modified
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+        items
+            .iter_mut()
+            .filter(|item| item.created_at == DateTime::UNIX_EPOCH)
+            .for_each(|item| item.created_at = written);
+    }
+    items
+        .iter_mut()
+        .filter(|item| item.updated_at == DateTime::UNIX_EPOCH)
+        .for_each(|item| item.updated_at = item.created_at);
+
+    let undated = |item: &ListItem| item.completed && item.completed_at.is_none();
+    if items.iter().any(undated) {
+        let events = read_events(&journal_path(path)).unwrap_or_default();
+        let times = completion_times(&events);
+        for item in items.iter_mut().filter(|item| undated(item)) {
+            item.completed_at = Some(times.get(&item.id).copied().unwrap_or(item.updated_at));
+        }
+    }
+    for item in items.iter_mut().filter(|item| !item.completed) {
+        item.completed_at = None;
+    }
+}
+
+/// Indices sorted, without repetitions
+fn unique(indices: &[usize]) -> Vec<usize> {
+    let mut indices = indices.to_vec();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seconds after 2024-01-01 00:00:00 UTC
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_704_067_200 + seconds, 0).unwrap()
+    }
+
+    fn descriptions(todo_list: &TodoList) -> Vec<&str> {
+        todo_list
+            .items()
+            .iter()
+            .map(|item| item.description.as_str())
+            .collect()
+    }
+
+    /// List of pending items with these descriptions, top first
+    fn list_of(descriptions: &[&str]) -> TodoList {
+        TodoList::new(
+            descriptions
+                .iter()
+                .enumerate()
+                .map(|(i, description)| ListItem {
+                    description: description.to_string(),
+                    created_at: at(i as i64),
+                    updated_at: at(i as i64),
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn created_items_go_to_the_top() {
+        let mut todo_list = TodoList::default();
+        assert!(todo_list.create("first", at(0)));
+        assert!(todo_list.create("second", at(1)));
+        assert_eq!(descriptions(&todo_list), ["second", "first"]);
+        assert_eq!(todo_list[0].created_at, at(1));
+        assert_eq!(todo_list[0].updated_at, at(1));
+        assert!(!todo_list[0].completed);
+    }
+
+    #[test]
+    fn blank_items_are_not_created() {
+        let mut todo_list = TodoList::default();
+        assert!(!todo_list.create("", at(0)));
+        assert!(!todo_list.create(" \t\n", at(0)));
+        assert!(todo_list.is_empty());
+        assert!(!todo_list.undo());
+    }
+
+    #[test]
+    fn inserted_items_keep_their_order() {
+        let mut todo_list = list_of(&["old"]);
+        let imported = list_of(&["a", "b"]).items().to_vec();
+        assert_eq!(todo_list.insert(imported), 2);
+        assert_eq!(descriptions(&todo_list), ["a", "b", "old"]);
+        assert_eq!(todo_list.insert(vec![]), 0);
+    }
+
+    #[test]
+    fn completed_state_is_set() {
+        let mut todo_list = list_of(&["a"]);
+        assert!(todo_list.set_completed(0, true));
+        assert!(todo_list[0].completed);
+        let completed_at = todo_list[0].completed_at.unwrap();
+        assert_eq!(todo_list[0].updated_at, completed_at);
+        assert!(!todo_list.set_completed(0, true));
+        assert_eq!(todo_list[0].completed_at, Some(completed_at));
+        assert!(todo_list.undo());
+        assert!(!todo_list[0].completed);
+        assert_eq!(todo_list[0].completed_at, None);
+        assert_eq!(todo_list[0].updated_at, at(0));
+        assert!(!todo_list.undo());
+    }
+
+    #[test]
+    fn description_is_replaced_unless_blank_or_the_same() {
+        let mut todo_list = list_of(&["a"]);
+        assert!(!todo_list.set_description(0, "  "));
+        assert!(!todo_list.set_description(0, "a"));
+        assert_eq!(todo_list[0].updated_at, at(0));
+        assert!(todo_list.set_description(0, "b"));
+        assert_eq!(descriptions(&todo_list), ["b"]);
+        assert!(todo_list[0].updated_at > at(0));
+    }
+
+    #[test]
+    fn toggling_flips_each_item_once() {
+        let mut todo_list = list_of(&["a", "b", "c"]);
+        todo_list.set_completed(1, true);
+        assert!(todo_list.toggle_completed(&[2, 1, 2]));
+        let completed = todo_list.items().iter().map(|item| item.completed);
+        assert_eq!(completed.collect::<Vec<bool>>(), [false, false, true]);
+        assert!(!todo_list.toggle_completed(&[]));
+    }
+
+    #[test]
+    fn duplicates_go_to_the_top_with_a_new_creation_date() {
+        let mut todo_list = list_of(&["a", "b", "c"]);
+        todo_list.set_completed(2, true);
+        assert_eq!(todo_list.duplicate(&[2, 0], at(60)), 2);
+        assert_eq!(descriptions(&todo_list), ["c", "a", "a", "b", "c"]);
+        assert!(todo_list[0].completed);
+        assert_eq!(todo_list[0].completed_at, Some(at(60)));
+        assert_eq!(todo_list[1].created_at, at(60));
+        assert_ne!(todo_list[0].id, todo_list[4].id);
+        assert_eq!(todo_list.index_of(todo_list[4].id), Some(4));
+    }
+
+    #[test]
+    fn deleting_removes_exactly_the_items() {
+        let mut todo_list = list_of(&["a", "b", "c", "d"]);
+        assert_eq!(todo_list.delete(&[3, 0, 3]), 2);
+        assert_eq!(descriptions(&todo_list), ["b", "c"]);
+        assert_eq!(todo_list.delete(&[]), 0);
+        assert!(todo_list.undo());
+        assert_eq!(descriptions(&todo_list), ["a", "b", "c", "d"]);
+    }
+
+    fn trashed(todo_list: &TodoList) -> Vec<&str> {
+        let trash = todo_list.trash().iter();
+        trash
+            .map(|trashed| trashed.item.description.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn deleted_items_go_to_the_trash_until_restored() {
+        let mut todo_list = list_of(&["a", "b", "c", "d"]);
+        todo_list.delete(&[2]);
+        todo_list.delete(&[2, 0]);
+        assert_eq!(trashed(&todo_list), ["a", "d", "c"]);
+        let ids = [todo_list.trash()[2].item.id, todo_list.trash()[0].item.id];
+        assert_eq!(todo_list.restore(&ids), 2);
+        assert_eq!(descriptions(&todo_list), ["a", "c", "b"]);
+        assert_eq!(trashed(&todo_list), ["d"]);
+        assert_eq!(todo_list.restore(&ids), 0);
+
+        assert!(todo_list.undo());
+        assert_eq!(descriptions(&todo_list), ["b"]);
+        assert_eq!(trashed(&todo_list), ["a", "d", "c"]);
+    }
+
+    #[test]
+    fn emptied_trash_cannot_be_undone() {
+        let mut todo_list = list_of(&["a", "b", "c"]);
+        let ids = [todo_list[0].id, todo_list[1].id];
+        todo_list.delete(&[0]);
+        todo_list.delete(&[0]);
+        assert_eq!(todo_list.empty_trash(), 2);
+        assert_eq!(todo_list.empty_trash(), 0);
+        let purged = todo_list.events.iter().rev().take(2);
+        assert!(purged.clone().all(|event| event.change == Change::Purged));
+        assert_eq!(
+            purged.map(|event| event.id).collect::<HashSet<ItemId>>(),
+            ids.into()
+        );
+
+        // Undoing the deletions leaves the list as it is
+        assert!(todo_list.undo());
+        assert!(todo_list.undo());
+        assert_eq!(descriptions(&todo_list), ["c"]);
+        assert!(todo_list.trash().is_empty());
+    }
+
+    #[test]
+    fn old_trash_is_purged() {
+        let mut todo_list = list_of(&["a", "b"]);
+        todo_list.delete(&[0]);
+        let now = Utc::now();
+        todo_list.delete(&[0]);
+        assert_eq!(todo_list.purge_trash(now), 1);
+        assert_eq!(trashed(&todo_list), ["b"]);
+        assert_eq!(todo_list.purge_trash(now), 0);
+        assert_eq!(logged(&todo_list).last(), Some(&Change::Purged));
+
+        // Undoing the last deletion leaves out the purged item
+        assert!(todo_list.undo());
+        assert_eq!(descriptions(&todo_list), ["b"]);
+        assert!(todo_list.trash().is_empty());
+    }
+
+    fn archived(todo_list: &TodoList) -> Vec<&str> {
+        let archive = todo_list.archived().iter();
+        archive
+            .map(|archived| archived.item.description.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn completed_items_are_archived_once_it_is_read() {
+        let mut todo_list = list_of(&["a", "b", "c"]);
+        todo_list.toggle_completed(&[0, 2]);
+        assert_eq!(todo_list.archive(&[0, 1, 2]), 0);
+
+        todo_list.load_archive("/nonexistent/todo.dat").unwrap();
+        assert_eq!(todo_list.archive(&[0, 1, 2]), 2);
+        assert_eq!(descriptions(&todo_list), ["b"]);
+        assert_eq!(archived(&todo_list), ["a", "c"]);
+        let c = todo_list.archived()[1].item.id;
+        assert_eq!(todo_list.unarchive(&[c, c]), 1);
+        assert_eq!(descriptions(&todo_list), ["c", "b"]);
+        assert_eq!(todo_list.unarchive(&[c]), 0);
+
+        assert!(todo_list.undo());
+        assert!(todo_list.undo());
+        assert_eq!(descriptions(&todo_list), ["a", "b", "c"]);
+        assert!(todo_list.archived().is_empty());
+        // The toggle, before the archive was read, is undone with the archive still read
+        assert!(todo_list.undo());
+        assert!(todo_list.archive.is_some());
+    }
+
+    #[test]
+    fn changes_are_undone_and_redone_in_order() {
+        let mut todo_list = TodoList::default();
+        todo_list.create("a", at(0));
+        todo_list.create("b", at(1));
+        assert!(todo_list.undo());
+        assert_eq!(descriptions(&todo_list), ["a"]);
+        assert!(todo_list.redo());
+        assert_eq!(descriptions(&todo_list), ["b", "a"]);
+        assert!(!todo_list.redo());
+    }
+
+    #[test]
+    fn a_new_change_clears_the_redo() {
+        let mut todo_list = TodoList::default();
+        todo_list.create("a", at(0));
+        todo_list.undo();
+        todo_list.create("b", at(1));
+        assert!(!todo_list.redo());
+    }
+
+    #[test]
+    fn filter_matches_a_prefix_ignoring_case() {
+        let todo_list = list_of(&["Buy milk", "call Bob", "buy bread"]);
+        assert_eq!(todo_list.filter("BUY", SortOrder::NewestFirst), [0, 2]);
+        assert_eq!(todo_list.filter("", SortOrder::NewestFirst), [0, 1, 2]);
+        assert!(todo_list.filter("milk", SortOrder::NewestFirst).is_empty());
+    }
+
+    #[test]
+    fn filter_sorts_the_rows() {
+        let mut todo_list = list_of(&["b", "C", "a"]);
+        todo_list.set_completed(0, true);
+        assert_eq!(todo_list.filter("", SortOrder::OldestFirst), [2, 1, 0]);
+        assert_eq!(todo_list.filter("", SortOrder::Description), [2, 0, 1]);
+        assert_eq!(todo_list.filter("", SortOrder::PendingFirst), [1, 2, 0]);
+    }
+
+    /// Kinds of the unsaved changes, oldest first
+    fn logged(todo_list: &TodoList) -> Vec<Change> {
+        let changes = todo_list.events.iter().map(|event| event.change.clone());
+        changes.collect()
+    }
+
+    #[test]
+    fn every_change_is_logged() {
+        let mut todo_list = list_of(&["a", "b"]);
+        let (a, b) = (todo_list[0].id, todo_list[1].id);
+        todo_list.set_completed(0, true);
+        todo_list.set_description(1, "B");
+        todo_list.toggle_completed(&[0]);
+        todo_list.delete(&[1]);
+        todo_list.create("c", at(2));
+        let c = todo_list[0].clone();
+        let ids = todo_list.events.iter().map(|event| event.id);
+        assert_eq!(ids.collect::<Vec<ItemId>>(), [a, b, a, b, c.id]);
+        assert_eq!(
+            logged(&todo_list),
+            [
+                Change::Completed,
+                Change::Renamed {
+                    description: "B".to_string()
+                },
+                Change::Uncompleted,
+                Change::Deleted,
+                Change::Created {
+                    item: c,
+                    position: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn undo_and_redo_are_logged() {
+        let mut todo_list = list_of(&["a"]);
+        let a = todo_list[0].clone();
+        todo_list.delete(&[0]);
+        todo_list.undo();
+        todo_list.redo();
+        let restored = Change::Restored {
+            item: a,
+            position: 0,
+        };
+        assert_eq!(
+            logged(&todo_list),
+            [Change::Deleted, restored.clone(), Change::Deleted]
+        );
+        todo_list.restore(&[todo_list.trash()[0].item.id]);
+        assert_eq!(logged(&todo_list)[3], restored);
+    }
+
+    #[test]
+    fn saved_lists_are_rebuilt_from_the_journal() {
+        let path = std::env::temp_dir()
+            .join(format!("todolist-core-list-{}.dat", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let mut todo_list = list_of(&["a", "b"]);
+        todo_list.save(&path).unwrap();
+        todo_list.set_description(0, "A");
+        todo_list.delete(&[1]);
+        todo_list.save(&path).unwrap();
+        assert!(todo_list.events.is_empty());
+        let loaded = TodoList::load(&path).unwrap();
+        assert_eq!(trashed(&loaded), ["b"]);
+
+        let id = todo_list[0].id;
+        todo_list.set_completed(0, true);
+        let timeline = todo_list.timeline(&path, id).unwrap();
+        let kinds = timeline.into_iter().map(|event| event.change);
+        assert!(matches!(
+            kinds.collect::<Vec<Change>>()[..],
+            [
+                Change::Created { position: 0, .. },
+                Change::Renamed { .. },
+                Change::Completed
+            ]
+        ));
+
+        std::fs::write(&path, "damaged").unwrap();
+        let rebuilt = TodoList::rebuild(&path).unwrap();
+        assert_eq!(descriptions(&rebuilt), ["A"]);
+        assert!(!rebuilt[0].completed);
+        assert_eq!(rebuilt[0].updated_at, loaded[0].updated_at);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(journal_path(&path)).unwrap();
+        std::fs::remove_file(trash_path(&path)).unwrap();
+    }
+
+    #[test]
+    fn new_files_leave_nothing_of_the_files_they_replace() {
+        let path = std::env::temp_dir()
+            .join(format!("todolist-core-new-{}.dat", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let mut todo_list = list_of(&["a", "b", "c"]);
+        todo_list.load_archive(&path).unwrap();
+        todo_list.set_completed(0, true);
+        assert_eq!(todo_list.archive(&[0]), 1);
+        todo_list.delete(&[0]);
+        todo_list.save(&path).unwrap();
+
+        let created = TodoList::new_file(&path).unwrap();
+        assert!(created.is_empty());
+        let mut loaded = TodoList::load(&path).unwrap();
+        assert!(loaded.is_empty() && loaded.trash().is_empty());
+        loaded.load_archive(&path).unwrap();
+        assert!(loaded.archived().is_empty());
+        let id = todo_list[0].id;
+        assert!(loaded.timeline(&path, id).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(journal_path(&path)).unwrap_or_default();
+    }
+
+    #[test]
+    fn damaged_journals_do_not_stop_saving() {
+        let path = std::env::temp_dir()
+            .join(format!("todolist-core-damaged-{}.dat", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        std::fs::write(journal_path(&path), "damaged\n").unwrap();
+        let mut todo_list = list_of(&["a"]);
+        todo_list.set_description(0, "A");
+        todo_list.save(&path).unwrap();
+        assert_eq!(descriptions(&TodoList::load(&path).unwrap()), ["A"]);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(journal_path(&path)).unwrap();
+    }
+
+    #[test]
+    fn completed_before_follows_the_completion_date() {
+        let mut todo_list = list_of(&["a", "b"]);
+        assert!(todo_list.completed_before(Utc::now()).is_empty());
+        todo_list.set_completed(1, true);
+        let completed_at = todo_list[1].completed_at.unwrap();
+        assert!(todo_list.completed_before(completed_at).is_empty());
+        assert_eq!(todo_list.completed_before(Utc::now()), [1]);
+    }
+
+    #[test]
+    fn data_files_of_earlier_versions_get_typed_timestamps() {
+        let path = std::env::temp_dir()
+            .join(format!("todolist-core-legacy-{}.dat", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        // Completed, description and creation date formatted for display
+        let legacy = [
+            (false, "a", "2024-03-01T10:00:00+02:00"),
+            (true, "b", "01-03-2024 • 10:00:00"),
+            (true, "c", "yesterday"),
+        ];
+        write_items(&path, &legacy).unwrap();
+        let written = DateTime::<Utc>::from(std::fs::metadata(&path).unwrap().modified().unwrap());
+
+        let loaded = TodoList::load(&path).unwrap();
+        let expected = DateTime::parse_from_rfc3339("2024-03-01T08:00:00Z").unwrap();
+        assert_eq!(loaded[0].created_at, expected);
+        assert_eq!(loaded[0].updated_at, expected);
+        assert_eq!(loaded[0].completed_at, None);
+        assert_eq!(loaded[1].created_at.date_naive().to_string(), "2024-03-01");
+        assert_eq!(loaded[1].completed_at, Some(loaded[1].updated_at));
+        assert_eq!(loaded[2].created_at, written);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn damaged_journals_do_not_stop_loading() {
+        let path = std::env::temp_dir()
+            .join(format!("todolist-core-unread-{}.dat", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let mut todo_list = list_of(&["a", "b"]);
+        todo_list.set_completed(1, true);
+        // Saved by an earlier version, which did not date completions
+        let mut items = todo_list.into_items();
+        items[1].completed_at = None;
+        write_items(&path, &items).unwrap();
+        std::fs::write(journal_path(&path), "damaged\n").unwrap();
+
+        let loaded = TodoList::load(&path).unwrap();
+        assert_eq!(descriptions(&loaded), ["a", "b"]);
+        assert_eq!(loaded[1].completed_at, Some(loaded[1].updated_at));
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(journal_path(&path)).unwrap();
+    }
+
+    /// Descriptions that broke the list when it was a tab-separated browser
+    const HOSTILE: [&str; 9] = [
+        "@C1 call Bob",
+        "@b meeting",
+        "@@ literal",
+        "tab\tseparated\tcolumns",
+        "two\nlines",
+        "שלום עולם",
+        "مرحبا بالعالم",
+        "🎉 party 👩‍👩‍👧",
+        "",
+    ];
+
+    fn hostile_list() -> TodoList {
+        let long = "x".repeat(100_000);
+        let mut todo_list = list_of(&HOSTILE);
+        todo_list.create(&long, at(99));
+        todo_list
+    }
+
+    #[test]
+    fn every_hostile_item_is_shown_without_a_filter() {
+        let todo_list = hostile_list();
+        for sort_order in SortOrder::ALL {
+            let mut visible = todo_list.filter("", sort_order);
+            visible.sort_unstable();
+            assert_eq!(visible, (0..todo_list.len()).collect::<Vec<usize>>());
+        }
+    }
+
+    #[test]
+    fn filter_matches_special_characters_literally() {
+        let todo_list = hostile_list();
+        let matches = |prefix| {
+            todo_list
+                .filter(prefix, SortOrder::NewestFirst)
+                .into_iter()
+                .map(|index| todo_list[index].description.as_str())
+                .collect::<Vec<&str>>()
+        };
+        assert_eq!(matches("@c1"), ["@C1 call Bob"]);
+        assert_eq!(matches("@@"), ["@@ literal"]);
+        assert_eq!(matches("tab\t"), ["tab\tseparated\tcolumns"]);
+        assert_eq!(matches("שלום"), ["שלום עולם"]);
+        assert_eq!(matches("🎉"), ["🎉 party 👩‍👩‍👧"]);
+        assert_eq!(matches("xxx").len(), 1);
+    }
+
+    #[test]
+    fn hostile_items_are_found_again() {
+        let todo_list = hostile_list();
+        for (index, item) in todo_list.items().iter().enumerate() {
+            assert_eq!(
+                todo_list.find(&item.description, item.created_at),
+                Some(index)
+            );
+        }
+        assert_eq!(todo_list.find("@C1 call Bob", at(1)), None);
+    }
+}
@@ -0,0 +1,614 @@
+//! The state of the main window as plain data, changed only by [`update`].
+//! The window sends a [`Message`] for each interaction and redraws itself from
+//! the new state, so interactions can be replayed and tested without a display
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    models::{ItemId, ListItem, SortOrder, TrashedItem},
+    todo_list::TodoList,
+};
+
+/// What the window shows, apart from its layout and colours
+#[derive(Default)]
+pub struct State {
+    pub todo_list: TodoList,
+    /// Prefix of the descriptions shown, ignoring case
+    pub filter: String,
+    pub sort_order: SortOrder,
+    /// Selected items, in the order they are shown
+    pub selection: Vec<ItemId>,
+    /// Selected item shown in the form
+    pub current: Option<ItemId>,
+}
+
+impl State {
+    pub fn new(todo_list: TodoList) -> Self {
+        Self {
+            todo_list,
+            ..Default::default()
+        }
+    }
+
+    /// Indices of the items shown, in their order
+    pub fn visible(&self) -> Vec<usize> {
+        self.todo_list.filter(&self.filter, self.sort_order)
+    }
+
+    /// Indices of the selected items that are still in the list
+    pub fn selected_indices(&self) -> Vec<usize> {
+        indices_of(&self.todo_list, &self.selection)
+    }
+
+    /// Index of the item shown in the form
+    pub fn current_index(&self) -> Option<usize> {
+        self.current.and_then(|id| self.todo_list.index_of(id))
+    }
+
+    /// Select these items only, the last one being shown in the form
+    fn select(&mut self, selection: Vec<ItemId>) {
+        self.current = selection.last().copied();
+        self.selection = selection;
+    }
+
+    fn clear_selection(&mut self) {
+        self.select(vec![]);
+    }
+
+    /// Keep only the selected items that are shown, in their order
+    fn retain_shown(&mut self) {
+        let selected = self.selection.iter().copied().collect::<HashSet<ItemId>>();
+        self.selection = ids_of(&self.todo_list, &self.visible())
+            .into_iter()
+            .filter(|id| selected.contains(id))
+            .collect();
+        if self.current.is_some_and(|id| !self.selection.contains(&id)) {
+            self.current = None;
+        }
+    }
+}
+
+/// An interaction, carrying everything needed to apply it
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// Add a pending item at the top, unless the description is blank
+    Create {
+        description: String,
+        created_at: DateTime<Utc>,
+    },
+    SetCompleted(ItemId, bool),
+    ToggleCompleted(Vec<ItemId>),
+    /// Replace the description of an item, unless the new one is blank
+    Rename(ItemId, String),
+    /// Copy the items at the top, created at the date
+    Duplicate(Vec<ItemId>, DateTime<Utc>),
+    /// Move the items to the trash
+    Delete(Vec<ItemId>),
+    /// Put trashed items back at the top
+    Restore(Vec<ItemId>),
+    EmptyTrash,
+    /// Move the completed items to the archive, which must be loaded
+    Archive(Vec<ItemId>),
+    /// Put archived items back at the top
+    Unarchive(Vec<ItemId>),
+    /// Add imported items at the top
+    Insert(Vec<ListItem>),
+    Undo,
+    Redo,
+    /// Replace the list and the trash, with no history,
+    /// as when another data file is opened
+    Load {
+        items: Vec<ListItem>,
+        trash: Vec<TrashedItem>,
+    },
+    SetFilter(String),
+    SetSortOrder(SortOrder),
+    /// Selection made in the table
+    Select {
+        selection: Vec<ItemId>,
+        current: Option<ItemId>,
+    },
+    SelectAll,
+    /// Select only the item shown above the current one, or the last one
+    SelectPrevious,
+    /// Select only the item shown below the current one, or the first one
+    SelectNext,
+}
+
+/// Work left to the window once the state is updated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Effect {
+    /// The list changed and should be written to the data file
+    Save,
+    /// The current item should be scrolled into view
+    ScrollToCurrent,
+}
+
+/// Apply the message to the state.
+/// Messages about items no longer in the list change nothing,
+/// and items that are not shown, like renamed ones the filter now hides, are unselected
+pub fn update(mut state: State, message: Message) -> (State, Vec<Effect>) {
+    let effects = match message {
+        Message::Create {
+            description,
+            created_at,
+        } => {
+            state.clear_selection();
+            saved(state.todo_list.create(&description, created_at))
+        }
+        Message::SetCompleted(id, completed) => match state.todo_list.index_of(id) {
+            Some(index) => {
+                state.select(vec![id]);
+                let mut effects = saved(state.todo_list.set_completed(index, completed));
+                effects.push(Effect::ScrollToCurrent);
+                effects
+            }
+            None => vec![],
+        },
+        Message::ToggleCompleted(ids) => {
+            let indices = indices_of(&state.todo_list, &ids);
+            match state.todo_list.toggle_completed(&indices) {
+                true => vec![Effect::Save, Effect::ScrollToCurrent],
+                false => vec![],
+            }
+        }
+        Message::Rename(id, description) => match state.todo_list.index_of(id) {
+            Some(index) if state.todo_list.set_description(index, &description) => {
+                state.select(vec![id]);
+                vec![Effect::Save, Effect::ScrollToCurrent]
+            }
+            _ => vec![],
+        },
+        Message::Duplicate(ids, created_at) => {
+            let indices = indices_of(&state.todo_list, &ids);
+            match state.todo_list.duplicate(&indices, created_at) {
+                0 => vec![],
+                // Copies go to the top, like created items
+                count => {
+                    let copies = state.todo_list.items()[..count]
+                        .iter()
+                        .map(|item| item.id)
+                        .collect();
+                    state.select(copies);
+                    vec![Effect::Save, Effect::ScrollToCurrent]
+                }
+            }
+        }
+        Message::Delete(ids) => {
+            let indices = indices_of(&state.todo_list, &ids);
+            match state.todo_list.delete(&indices) {
+                0 => vec![],
+                _ => {
+                    state.clear_selection();
+                    vec![Effect::Save]
+                }
+            }
+        }
+        Message::Restore(ids) => {
+            let count = state.todo_list.restore(&ids);
+            select_top(&mut state, count)
+        }
+        Message::EmptyTrash => saved(state.todo_list.empty_trash() > 0),
+        Message::Archive(ids) => {
+            let indices = indices_of(&state.todo_list, &ids);
+            match state.todo_list.archive(&indices) {
+                0 => vec![],
+                _ => {
+                    state.clear_selection();
+                    vec![Effect::Save]
+                }
+            }
+        }
+        Message::Unarchive(ids) => {
+            let count = state.todo_list.unarchive(&ids);
+            select_top(&mut state, count)
+        }
+        Message::Insert(items) => {
+            state.clear_selection();
+            saved(state.todo_list.insert(items) > 0)
+        }
+        Message::Undo => match state.todo_list.undo() {
+            true => {
+                state.clear_selection();
+                vec![Effect::Save]
+            }
+            false => vec![],
+        },
+        Message::Redo => match state.todo_list.redo() {
+            true => {
+                state.clear_selection();
+                vec![Effect::Save]
+            }
+            false => vec![],
+        },
+        Message::Load { items, trash } => {
+            state.todo_list = TodoList::with_trash(items, trash);
+            state.clear_selection();
+            vec![]
+        }
+        Message::SetFilter(filter) => {
+            state.filter = filter;
+            state.clear_selection();
+            vec![]
+        }
+        Message::SetSortOrder(sort_order) => {
+            state.sort_order = sort_order;
+            vec![Effect::ScrollToCurrent]
+        }
+        Message::Select { selection, current } => {
+            state.selection = selection;
+            state.current = current;
+            vec![]
+        }
+        Message::SelectAll => {
+            state.selection = ids_of(&state.todo_list, &state.visible());
+            vec![]
+        }
+        Message::SelectPrevious | Message::SelectNext => {
+            let visible = state.visible();
+            let Some(last) = visible.len().checked_sub(1) else {
+                return (state, vec![]);
+            };
+            let current = state
+                .current_index()
+                .and_then(|index| visible.iter().position(|&shown| shown == index));
+            let row = match (current, message) {
+                (Some(row), Message::SelectPrevious) => row.saturating_sub(1),
+                (Some(row), _) => (row + 1).min(last),
+                (None, Message::SelectPrevious) => last,
+                (None, _) => 0,
+            };
+            state.select(vec![state.todo_list[visible[row]].id]);
+            vec![Effect::ScrollToCurrent]
+        }
+    };
+
+    if !state.selection.is_empty() {
+        state.retain_shown();
+    }
+    (state, effects)
+}
+
+/// Select the items put back at the top, as restored or unarchived ones
+fn select_top(state: &mut State, count: usize) -> Vec<Effect> {
+    if count == 0 {
+        return vec![];
+    }
+    let ids = state.todo_list.items()[..count]
+        .iter()
+        .map(|item| item.id)
+        .collect();
+    state.select(ids);
+    vec![Effect::Save, Effect::ScrollToCurrent]
+}
+
+fn saved(changed: bool) -> Vec<Effect> {
+    match changed {
+        true => vec![Effect::Save],
+        false => vec![],
+    }
+}
+
+/// Indices of the items still in the list, in the order of the ids.
+/// The list is read once, however many ids there are
+fn indices_of(todo_list: &TodoList, ids: &[ItemId]) -> Vec<usize> {
+    let indices = todo_list
+        .items()
+        .iter()
+        .enumerate()
+        .map(|(index, item)| (item.id, index))
+        .collect::<HashMap<ItemId, usize>>();
+    ids.iter()
+        .filter_map(|id| indices.get(id).copied())
+        .collect()
+}
+
+fn ids_of(todo_list: &TodoList, indices: &[usize]) -> Vec<ItemId> {
+    indices.iter().map(|&index| todo_list[index].id).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// State of a list of pending items with these descriptions, top first
+    fn state_of(descriptions: &[&str]) -> State {
+        State::new(TodoList::new(
+            descriptions
+                .iter()
+                .map(|description| ListItem {
+                    description: description.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+        ))
+    }
+
+    /// Apply the messages in turn, collecting the effects
+    fn replay(mut state: State, messages: Vec<Message>) -> (State, Vec<Effect>) {
+        let mut all_effects = vec![];
+        for message in messages {
+            let effects;
+            (state, effects) = update(state, message);
+            all_effects.extend(effects);
+        }
+        (state, all_effects)
+    }
+
+    fn id(state: &State, index: usize) -> ItemId {
+        state.todo_list[index].id
+    }
+
+    fn descriptions(state: &State) -> Vec<&str> {
+        state
+            .todo_list
+            .items()
+            .iter()
+            .map(|item| item.description.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn created_items_are_saved() {
+        let state = state_of(&["a"]);
+        let selected = Message::SelectAll;
+        let create = Message::Create {
+            description: "b".to_string(),
+            created_at: Utc::now(),
+        };
+        let (state, effects) = replay(state, vec![selected, create]);
+        assert_eq!(descriptions(&state), ["b", "a"]);
+        assert_eq!(effects, [Effect::Save]);
+        assert!(state.selection.is_empty());
+    }
+
+    #[test]
+    fn blank_items_change_nothing() {
+        let create = Message::Create {
+            description: " ".to_string(),
+            created_at: Utc::now(),
+        };
+        let (state, effects) = update(state_of(&["a"]), create);
+        assert_eq!(descriptions(&state), ["a"]);
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn toggling_keeps_the_selection() {
+        let state = state_of(&["a", "b", "c"]);
+        let (a, c) = (id(&state, 0), id(&state, 2));
+        let select = Message::Select {
+            selection: vec![a, c],
+            current: Some(a),
+        };
+        let toggle = Message::ToggleCompleted(vec![a, c]);
+        let (state, effects) = replay(state, vec![select, toggle]);
+        let completed = state.todo_list.items().iter().map(|item| item.completed);
+        assert_eq!(completed.collect::<Vec<bool>>(), [true, false, true]);
+        assert_eq!(effects, [Effect::Save, Effect::ScrollToCurrent]);
+        assert_eq!(state.selection, [a, c]);
+        assert_eq!(state.current, Some(a));
+    }
+
+    #[test]
+    fn ids_are_found_in_the_order_given() {
+        let state = state_of(&["a", "b", "c"]);
+        let (a, c) = (id(&state, 0), id(&state, 2));
+        assert_eq!(indices_of(&state.todo_list, &[c, ItemId::new(), a]), [2, 0]);
+    }
+
+    #[test]
+    fn large_selections_are_toggled() {
+        let descriptions = (0..50_000).map(|i| i.to_string()).collect::<Vec<String>>();
+        let state = state_of(
+            &descriptions
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<&str>>(),
+        );
+        let (state, _) = replay(state, vec![Message::SelectAll]);
+        let toggle = Message::ToggleCompleted(state.selection.clone());
+        let (state, _) = update(state, toggle);
+        assert!(state.todo_list.items().iter().all(|item| item.completed));
+        assert_eq!(state.selected_indices().len(), 50_000);
+    }
+
+    #[test]
+    fn the_form_sets_the_completed_state_of_its_item() {
+        let state = state_of(&["a", "b"]);
+        let b = id(&state, 1);
+        let (state, effects) = update(state, Message::SetCompleted(b, true));
+        assert!(state.todo_list[1].completed);
+        assert_eq!(effects, [Effect::Save, Effect::ScrollToCurrent]);
+        assert_eq!(state.current, Some(b));
+        let (_, effects) = update(state, Message::SetCompleted(b, true));
+        assert_eq!(effects, [Effect::ScrollToCurrent]);
+    }
+
+    #[test]
+    fn renamed_items_are_selected() {
+        let state = state_of(&["a", "b"]);
+        let b = id(&state, 1);
+        let (state, effects) = update(state, Message::Rename(b, "c".to_string()));
+        assert_eq!(descriptions(&state), ["a", "c"]);
+        assert_eq!(effects, [Effect::Save, Effect::ScrollToCurrent]);
+        assert_eq!(state.selection, [b]);
+        let (_, effects) = update(state, Message::Rename(b, "".to_string()));
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn duplicates_are_selected() {
+        let state = state_of(&["a", "b", "c"]);
+        let ids = vec![id(&state, 2), id(&state, 0)];
+        let (state, effects) = update(state, Message::Duplicate(ids, Utc::now()));
+        assert_eq!(descriptions(&state), ["c", "a", "a", "b", "c"]);
+        assert_eq!(effects, [Effect::Save, Effect::ScrollToCurrent]);
+        assert_eq!(state.selection, [id(&state, 0), id(&state, 1)]);
+        assert_eq!(state.current, Some(id(&state, 1)));
+    }
+
+    #[test]
+    fn deleting_clears_the_selection_until_undone() {
+        let state = state_of(&["a", "b", "c"]);
+        let b = id(&state, 1);
+        let select = Message::Select {
+            selection: vec![b],
+            current: Some(b),
+        };
+        let (state, effects) = replay(state, vec![select, Message::Delete(vec![b])]);
+        assert_eq!(descriptions(&state), ["a", "c"]);
+        assert_eq!(effects, [Effect::Save]);
+        assert_eq!(state.current, None);
+        let (state, effects) = update(state, Message::Undo);
+        assert_eq!(descriptions(&state), ["a", "b", "c"]);
+        assert_eq!(effects, [Effect::Save]);
+        assert_eq!(state.todo_list.index_of(b), Some(1));
+    }
+
+    #[test]
+    fn restored_items_are_selected() {
+        let state = state_of(&["a", "b", "c"]);
+        let (a, c) = (id(&state, 0), id(&state, 2));
+        let (state, _) = update(state, Message::Delete(vec![a, c]));
+        let (state, effects) = update(state, Message::Restore(vec![c]));
+        assert_eq!(descriptions(&state), ["c", "b"]);
+        assert_eq!(effects, [Effect::Save, Effect::ScrollToCurrent]);
+        assert_eq!(state.selection, [c]);
+        let (state, effects) = update(state, Message::Restore(vec![c]));
+        assert!(effects.is_empty());
+
+        let (state, effects) = update(state, Message::EmptyTrash);
+        assert_eq!(effects, [Effect::Save]);
+        assert!(state.todo_list.trash().is_empty());
+        let (_, effects) = update(state, Message::Restore(vec![a]));
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn only_completed_items_are_archived_and_unarchived_items_selected() {
+        let state = state_of(&["a", "b"]);
+        let (a, b) = (id(&state, 0), id(&state, 1));
+        let (mut state, _) = update(state, Message::SetCompleted(b, true));
+        state
+            .todo_list
+            .load_archive("/nonexistent/todo.dat")
+            .unwrap();
+        let (state, effects) = update(state, Message::Archive(vec![a, b]));
+        assert_eq!(descriptions(&state), ["a"]);
+        assert_eq!(effects, [Effect::Save]);
+        assert!(state.selection.is_empty());
+
+        let (state, effects) = update(state, Message::Unarchive(vec![b]));
+        assert_eq!(descriptions(&state), ["b", "a"]);
+        assert_eq!(effects, [Effect::Save, Effect::ScrollToCurrent]);
+        assert_eq!(state.selection, [b]);
+    }
+
+    #[test]
+    fn messages_about_deleted_items_change_nothing() {
+        let state = state_of(&["a", "b"]);
+        let a = id(&state, 0);
+        let (state, _) = update(state, Message::Delete(vec![a]));
+        let messages = vec![
+            Message::SetCompleted(a, true),
+            Message::ToggleCompleted(vec![a]),
+            Message::Rename(a, "c".to_string()),
+            Message::Duplicate(vec![a], Utc::now()),
+            Message::Delete(vec![a]),
+        ];
+        let (state, effects) = replay(state, messages);
+        assert_eq!(descriptions(&state), ["b"]);
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn sorting_keeps_the_selection_but_filtering_clears_it() {
+        let state = state_of(&["b", "a"]);
+        let b = id(&state, 0);
+        let select = Message::Select {
+            selection: vec![b],
+            current: Some(b),
+        };
+        let sort = Message::SetSortOrder(SortOrder::Description);
+        let (state, effects) = replay(state, vec![select, sort]);
+        assert_eq!(state.visible(), [1, 0]);
+        assert_eq!(effects, [Effect::ScrollToCurrent]);
+        assert_eq!(state.current, Some(b));
+        let (state, _) = update(state, Message::SetFilter("A".to_string()));
+        assert_eq!(state.visible(), [1]);
+        assert_eq!(state.current, None);
+    }
+
+    #[test]
+    fn items_filtered_out_are_unselected() {
+        let state = state_of(&["buy milk", "buy bread"]);
+        let milk = id(&state, 0);
+        let filter = Message::SetFilter("buy".to_string());
+        let rename = Message::Rename(milk, "drink milk".to_string());
+        let (state, effects) = replay(state, vec![filter, rename]);
+        assert_eq!(effects, [Effect::Save, Effect::ScrollToCurrent]);
+        assert!(state.selection.is_empty());
+        assert_eq!(state.current, None);
+    }
+
+    #[test]
+    fn selection_follows_the_order_shown() {
+        let state = state_of(&["b", "a"]);
+        let (b, a) = (id(&state, 0), id(&state, 1));
+        let select = Message::Select {
+            selection: vec![b, a],
+            current: Some(b),
+        };
+        let sort = Message::SetSortOrder(SortOrder::Description);
+        let (state, _) = replay(state, vec![select, sort]);
+        assert_eq!(state.selection, [a, b]);
+        assert_eq!(state.selected_indices(), [1, 0]);
+    }
+
+    #[test]
+    fn select_all_selects_the_rows_shown() {
+        let state = state_of(&["buy milk", "call Bob", "buy bread"]);
+        let filter = Message::SetFilter("buy".to_string());
+        let (state, _) = replay(state, vec![filter, Message::SelectAll]);
+        assert_eq!(state.selection, [id(&state, 0), id(&state, 2)]);
+        assert_eq!(state.selected_indices(), [0, 2]);
+    }
+
+    #[test]
+    fn arrows_walk_the_rows_shown_and_stop_at_the_ends() {
+        let state = state_of(&["b", "c", "a"]);
+        let sort = Message::SetSortOrder(SortOrder::Description);
+        let (state, _) = replay(state, vec![sort, Message::SelectNext]);
+        assert_eq!(state.current_index(), Some(2));
+        let (state, effects) = replay(state, vec![Message::SelectNext, Message::SelectNext]);
+        assert_eq!(state.current_index(), Some(1));
+        assert_eq!(effects, [Effect::ScrollToCurrent, Effect::ScrollToCurrent]);
+        let (state, _) = update(state, Message::SelectPrevious);
+        assert_eq!(state.selected_indices(), [0]);
+
+        let (state, _) = update(state, Message::SetFilter(String::new()));
+        let (state, _) = update(state, Message::SelectPrevious);
+        assert_eq!(state.current_index(), Some(1));
+        let (_, effects) = update(state_of(&[]), Message::SelectNext);
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn loading_replaces_the_list_without_history() {
+        let state = state_of(&["a"]);
+        let items = state_of(&["b", "c"]).todo_list.items().to_vec();
+        let load = Message::Load {
+            items,
+            trash: vec![],
+        };
+        let (state, effects) = replay(state, vec![Message::SelectAll, load]);
+        assert_eq!(descriptions(&state), ["b", "c"]);
+        assert!(effects.is_empty());
+        assert!(state.selection.is_empty());
+        let (_, effects) = update(state, Message::Undo);
+        assert!(effects.is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+use std::{env, fs, process};
+
+use chrono::{DateTime, Utc};
+use proptest::prelude::*;
+use todolist_core::{ListItem, SortOrder, TodoList};
+
+fn item() -> impl Strategy<Value = ListItem> {
+    (
+        any::<bool>(),
+        any::<String>(),
+        1..4_000_000_000i64,
+        proptest::option::of(proptest::char::range('A', 'Z')),
+        proptest::collection::vec("[a-z]{1,8}", 0..3),
+        0..4usize,
+    )
+        .prop_map(|(completed, description, seconds, priority, tags, depth)| {
+            let created_at = DateTime::from_timestamp(seconds, 0).unwrap();
+            ListItem {
+                completed,
+                description,
+                created_at,
+                priority,
+                tags,
+                depth,
+                updated_at: created_at,
+                completed_at: completed.then_some(created_at),
+                ..Default::default()
+            }
+        })
+}
+
+fn items() -> impl Strategy<Value = Vec<ListItem>> {
+    proptest::collection::vec(item(), 0..30)
+}
+
+fn sort_order() -> impl Strategy<Value = SortOrder> {
+    proptest::sample::select(SortOrder::ALL.to_vec())
+}
+
+/// A change to the list, with indices taken modulo its length
+#[derive(Clone, Debug)]
+enum Change {
+    Create(String),
+    SetCompleted(usize, bool),
+    SetDescription(usize, String),
+    Toggle(Vec<usize>),
+    Duplicate(Vec<usize>),
+    Delete(Vec<usize>),
+}
+
+fn change() -> impl Strategy<Value = Change> {
+    let indices = || proptest::collection::vec(any::<usize>(), 0..4);
+    prop_oneof![
+        any::<String>().prop_map(Change::Create),
+        (any::<usize>(), any::<bool>()).prop_map(|(i, c)| Change::SetCompleted(i, c)),
+        (any::<usize>(), any::<String>()).prop_map(|(i, d)| Change::SetDescription(i, d)),
+        indices().prop_map(Change::Toggle),
+        indices().prop_map(Change::Duplicate),
+        indices().prop_map(Change::Delete),
+    ]
+}
+
+/// Apply the change, returning whether the list changed
+fn apply(todo_list: &mut TodoList, change: &Change) -> bool {
+    let len = todo_list.len();
+    let wrap = |indices: &[usize]| match len {
+        0 => vec![],
+        _ => indices.iter().map(|i| i % len).collect::<Vec<usize>>(),
+    };
+    match change {
+        Change::Create(description) => todo_list.create(description, Utc::now()),
+        Change::SetCompleted(i, completed) if len > 0 => {
+            todo_list.set_completed(i % len, *completed)
+        }
+        Change::SetDescription(i, description) if len > 0 => {
+            todo_list.set_description(i % len, description)
+        }
+        Change::Toggle(indices) => todo_list.toggle_completed(&wrap(indices)),
+        Change::Duplicate(indices) => todo_list.duplicate(&wrap(indices), Utc::now()) > 0,
+        Change::Delete(indices) => todo_list.delete(&wrap(indices)) > 0,
+        _ => false,
+    }
+}
+
+proptest! {
+    #[test]
+    fn filter_shows_each_match_once(items in items(), prefix in ".{0,2}", order in sort_order()) {
+        let todo_list = TodoList::new(items.clone());
+        let mut visible = todo_list.filter(&prefix, order);
+        visible.sort_unstable();
+        let prefix = prefix.to_lowercase();
+        let expected = (0..items.len())
+            .filter(|&i| items[i].description.to_lowercase().starts_with(&prefix))
+            .collect::<Vec<usize>>();
+        prop_assert_eq!(visible, expected);
+    }
+
+    #[test]
+    fn filter_respects_the_sort_order(items in items(), order in sort_order()) {
+        let todo_list = TodoList::new(items);
+        let visible = todo_list.filter("", order);
+        for pair in visible.windows(2) {
+            let (a, b) = (&todo_list[pair[0]], &todo_list[pair[1]]);
+            match order {
+                SortOrder::NewestFirst => prop_assert!(pair[0] < pair[1]),
+                SortOrder::OldestFirst => prop_assert!(pair[0] > pair[1]),
+                SortOrder::Description => prop_assert!(
+                    a.description.to_lowercase() <= b.description.to_lowercase()
+                ),
+                SortOrder::PendingFirst => {
+                    prop_assert!(a.completed <= b.completed);
+                    if a.completed == b.completed {
+                        prop_assert!(pair[0] < pair[1]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_change_is_undone(items in items(), changes in proptest::collection::vec(change(), 0..12)) {
+        let mut todo_list = TodoList::new(items.clone());
+        let mut states = vec![items];
+        for change in &changes {
+            if apply(&mut todo_list, change) {
+                states.push(todo_list.items().to_vec());
+            }
+        }
+        let last = states.pop().unwrap();
+        prop_assert_eq!(todo_list.items(), &last[..]);
+        while let Some(previous) = states.pop() {
+            prop_assert!(todo_list.undo());
+            prop_assert_eq!(todo_list.items(), &previous[..]);
+        }
+        prop_assert!(!todo_list.undo());
+    }
+
+    #[test]
+    fn undone_changes_are_redone(items in items(), changes in proptest::collection::vec(change(), 0..12)) {
+        let mut todo_list = TodoList::new(items);
+        for change in &changes {
+            apply(&mut todo_list, change);
+        }
+        let last = todo_list.items().to_vec();
+        let mut undone = 0;
+        while todo_list.undo() {
+            undone += 1;
+        }
+        for _ in 0..undone {
+            prop_assert!(todo_list.redo());
+        }
+        prop_assert!(!todo_list.redo());
+        prop_assert_eq!(todo_list.items(), &last[..]);
+    }
+
+    #[test]
+    fn delete_keeps_the_other_items_in_order(items in items(), picks in proptest::collection::vec(any::<usize>(), 0..10)) {
+        let mut todo_list = TodoList::new(items.clone());
+        let indices = match items.len() {
+            0 => vec![],
+            len => picks.iter().map(|i| i % len).collect(),
+        };
+        todo_list.delete(&indices);
+        let kept = items
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !indices.contains(i))
+            .map(|(_, item)| item)
+            .collect::<Vec<ListItem>>();
+        prop_assert_eq!(todo_list.items(), &kept[..]);
+    }
+
+    #[test]
+    fn created_items_are_found(items in items(), description in ".*\\S.*") {
+        let mut todo_list = TodoList::new(items);
+        let now = Utc::now();
+        prop_assert!(todo_list.create(&description, now));
+        prop_assert_eq!(todo_list.find(&description, now), Some(0));
+    }
+
+    #[test]
+    fn saved_lists_load_unchanged(items in items()) {
+        let path = env::temp_dir()
+            .join(format!("todolist-core-properties-{}.dat", process::id()))
+            .to_string_lossy()
+            .into_owned();
+        TodoList::new(items.clone()).save(&path).unwrap();
+        let loaded = TodoList::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        // An empty list has no journal
+        fs::remove_file(format!("{path}.journal")).unwrap_or_default();
+        prop_assert_eq!(loaded.items(), &items[..]);
+    }
+
+    #[test]
+    fn journals_rebuild_the_saved_list(
+        items in items(),
+        changes in proptest::collection::vec((change(), 0..3usize), 0..12),
+    ) {
+        let path = env::temp_dir()
+            .join(format!("todolist-core-properties-journal-{}.dat", process::id()))
+            .to_string_lossy()
+            .into_owned();
+        let mut todo_list = TodoList::new(items);
+        todo_list.save(&path).unwrap();
+        // Each change is followed by an undo, a redo or nothing, and a save now and then
+        for (i, (change, then)) in changes.iter().enumerate() {
+            apply(&mut todo_list, change);
+            match then {
+                0 => {}
+                1 => { todo_list.undo(); }
+                _ => { todo_list.redo(); }
+            }
+            if i % 3 == 0 {
+                todo_list.save(&path).unwrap();
+            }
+        }
+        todo_list.save(&path).unwrap();
+        let rebuilt = TodoList::rebuild(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        // An empty list has no journal, and an empty trash no file
+        fs::remove_file(format!("{path}.journal")).unwrap_or_default();
+        fs::remove_file(format!("{path}.trash")).unwrap_or_default();
+        prop_assert_eq!(rebuilt.items(), todo_list.items());
+    }
+}